@@ -1,5 +1,8 @@
 //! Buffer management for LaserCube devices.
 
+#[cfg(feature = "alloc")]
+use crate::LaserInfo;
+
 /// Default buffer size from observed devices.
 pub const DEFAULT_SIZE: u16 = 6_000;
 /// Recommended buffer threshold for maintaining stability vs latency
@@ -7,6 +10,7 @@ pub const DEFAULT_THRESHOLD: u16 = 5_000;
 
 /// Tracks the state of the LaserCube's buffer.
 #[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BufferState {
     /// Total buffer size.
     pub total_size: u16,
@@ -31,6 +35,20 @@ impl BufferState {
         Self::DEFAULT
     }
 
+    /// Create a `BufferState` from the buffer info reported by the device.
+    ///
+    /// The threshold is derived from the total size as in
+    /// [`BufferState::update_total_size`].
+    ///
+    /// Requires the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn from_laser_info(info: &LaserInfo, current_time: u64) -> Self {
+        let mut state = Self::new();
+        state.update_total_size(info.header.rx_buffer_size);
+        state.update_free_space(info.header.rx_buffer_free, current_time);
+        state
+    }
+
     /// Update buffer free space from device response.
     pub fn update_free_space(&mut self, free_space: u16, current_time: u64) {
         self.free_space = free_space;
@@ -51,23 +69,84 @@ impl BufferState {
         }
     }
 
+    /// Set the threshold so that `should_send` keeps the backlog of points
+    /// queued on the device under the given latency.
+    ///
+    /// The threshold is clamped to `[0, total_size]`. If `dac_rate` is `0`, the
+    /// threshold is left unchanged.
+    pub fn set_latency_target(&mut self, max_latency_ms: u16, dac_rate: u32) {
+        if dac_rate == 0 {
+            return;
+        }
+        let max_backlog = dac_rate as u64 * max_latency_ms as u64 / 1000;
+        let max_backlog = max_backlog.min(self.total_size as u64) as u16;
+        self.threshold = self.total_size - max_backlog;
+    }
+
+    /// The free space reported by the device, limited to the portion of the
+    /// buffer that can be filled without exceeding `max_latency_ms` of queued
+    /// points at `dac_rate`.
+    ///
+    /// Uses saturating arithmetic throughout, so inconsistent inputs (e.g. a
+    /// reported free space exceeding the buffer size) never overflow. If
+    /// `dac_rate` is `0`, the latency is not limited and `rx_buffer_free` is
+    /// returned.
+    pub fn latency_capped_free(
+        rx_buffer_size: u16,
+        rx_buffer_free: u16,
+        dac_rate: u32,
+        max_latency_ms: u16,
+    ) -> u16 {
+        if dac_rate == 0 {
+            return rx_buffer_free;
+        }
+        let max_buffered = dac_rate as u64 * max_latency_ms as u64 / 1000;
+        let max_buffered = max_buffered.min(rx_buffer_size as u64) as u16;
+        let excluded = rx_buffer_size.saturating_sub(max_buffered);
+        rx_buffer_free.saturating_sub(excluded)
+    }
+
     /// Check if we should send more data based on buffer free space.
     pub fn should_send(&self) -> bool {
         self.free_space >= self.threshold
     }
 
     /// Estimate current free space based on time elapsed and DAC rate.
+    ///
+    /// If `current_time` is earlier than the last update, no time is assumed
+    /// to have elapsed. See
+    /// [`BufferState::estimate_current_free_space_with_wrap`] for handling
+    /// time sources that may restart.
     pub fn estimate_current_free_space(&self, current_time: u64, dac_rate: u32) -> u16 {
+        self.estimate_current_free_space_with_wrap(current_time, dac_rate, u64::MAX)
+    }
+
+    /// Estimate current free space based on time elapsed and DAC rate,
+    /// tolerating a time source that may step backwards or restart.
+    ///
+    /// If `current_time` is earlier than the last update by at most
+    /// `wrap_threshold` milliseconds, the step is treated as clock jitter and
+    /// no time is assumed to have elapsed. A larger backward step is treated
+    /// as the time source restarting from zero, e.g. a reset millisecond
+    /// counter on an embedded timer, so `current_time` milliseconds are
+    /// assumed to have elapsed. The estimate is then a lower bound, and the
+    /// state should be re-synced with the device via
+    /// [`BufferState::update_free_space`].
+    pub fn estimate_current_free_space_with_wrap(
+        &self,
+        current_time: u64,
+        dac_rate: u32,
+        wrap_threshold: u64,
+    ) -> u16 {
         if dac_rate == 0 || self.last_update_time == 0 {
             return self.free_space;
         }
 
-        // Calculate time delta in milliseconds
-        let delta_ms = if current_time > self.last_update_time {
-            current_time - self.last_update_time
-        } else {
-            // Handle possible timer wraparound
-            0
+        // Calculate time delta in milliseconds, handling jitter and restarts
+        let delta_ms = match current_time.checked_sub(self.last_update_time) {
+            Some(delta_ms) => delta_ms,
+            None if self.last_update_time - current_time <= wrap_threshold => 0,
+            None => current_time,
         };
 
         // Convert from DAC rate (points per second) to points per millisecond
@@ -77,12 +156,37 @@ impl BufferState {
         let points_consumed = (delta_ms as f32 * points_per_ms) as u16;
 
         // Add to free space, but don't exceed total buffer size
-        let estimated_free = self
-            .free_space
+        self.free_space
             .saturating_add(points_consumed)
-            .min(self.total_size);
+            .min(self.total_size)
+    }
+
+    /// The fraction of the buffer occupied by queued points, in the range
+    /// `[0.0, 1.0]`.
+    ///
+    /// Returns `0.0` if the total size is `0`.
+    pub fn fill_fraction(&self) -> f32 {
+        if self.total_size == 0 {
+            return 0.0;
+        }
+        self.queued_points() as f32 / self.total_size as f32
+    }
+
+    /// Estimate the time in milliseconds until the points currently queued on
+    /// the device are rendered at the given DAC rate.
+    ///
+    /// Returns `0.0` if the DAC rate is `0`.
+    pub fn latency_ms(&self, dac_rate: u32) -> f32 {
+        if dac_rate == 0 {
+            return 0.0;
+        }
+        let points_per_ms = dac_rate as f32 / 1000.0;
+        self.queued_points() as f32 / points_per_ms
+    }
 
-        estimated_free
+    /// The number of points queued on the device.
+    fn queued_points(&self) -> u16 {
+        self.total_size.saturating_sub(self.free_space)
     }
 
     /// Update the buffer when points are sent.
@@ -111,6 +215,42 @@ mod tests {
         assert_eq!(buffer.last_update_time, 0);
     }
 
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_from_laser_info() {
+        use crate::{ConnectionType, LaserInfoHeader, StatusFlags};
+
+        let mut info = LaserInfo {
+            header: LaserInfoHeader {
+                fw_major: 0,
+                fw_minor: 13,
+                status: StatusFlags::empty(),
+                dac_rate: 30_000,
+                max_dac_rate: 40_000,
+                rx_buffer_free: 4500,
+                rx_buffer_size: 8000,
+                battery_percent: 100,
+                temperature: 30,
+                model_number: 1,
+                conn_type: ConnectionType::Wifi,
+                serial_number: [1, 2, 3, 4, 5, 6],
+                ip_addr: [192, 168, 1, 100].into(),
+            },
+            model_name: "LaserCube".to_string(),
+        };
+        let buffer = BufferState::from_laser_info(&info, 100);
+        assert_eq!(buffer.total_size, 8000);
+        assert_eq!(buffer.free_space, 4500);
+        assert_eq!(buffer.threshold, 7000); // 8000 - 1000
+        assert_eq!(buffer.last_update_time, 100);
+
+        // Small buffers use the fallback threshold.
+        info.header.rx_buffer_size = 600;
+        info.header.rx_buffer_free = 600;
+        let buffer = BufferState::from_laser_info(&info, 200);
+        assert_eq!(buffer.threshold, 500); // 600 / 6 * 5
+    }
+
     #[test]
     fn test_update_free_space() {
         let mut buffer = BufferState::new();
@@ -141,6 +281,74 @@ mod tests {
         assert_eq!(buffer.threshold, 500); // 600 / 6 * 5
     }
 
+    #[test]
+    fn test_set_latency_target() {
+        let mut buffer = BufferState::new();
+
+        // 30000 points per second for 64ms = 1920 points
+        buffer.set_latency_target(64, 30_000);
+        assert_eq!(buffer.threshold, DEFAULT_SIZE - 1920);
+        buffer.free_space = DEFAULT_SIZE - 1920;
+        assert!(buffer.should_send());
+        buffer.free_space = DEFAULT_SIZE - 1921;
+        assert!(!buffer.should_send());
+
+        // Zero DAC rate leaves the threshold unchanged
+        buffer.set_latency_target(64, 0);
+        assert_eq!(buffer.threshold, DEFAULT_SIZE - 1920);
+
+        // Latencies beyond the buffer size clamp the threshold to 0
+        buffer.set_latency_target(u16::MAX, 30_000);
+        assert_eq!(buffer.threshold, 0);
+
+        // Zero latency only sends into an empty buffer
+        buffer.set_latency_target(0, 30_000);
+        assert_eq!(buffer.threshold, DEFAULT_SIZE);
+    }
+
+    #[test]
+    fn test_fill_and_latency() {
+        let mut buffer = BufferState::new();
+        buffer.total_size = 6000;
+        buffer.free_space = 3000;
+        assert_eq!(buffer.fill_fraction(), 0.5);
+        assert_eq!(buffer.latency_ms(30_000), 100.0);
+        assert_eq!(buffer.latency_ms(0), 0.0);
+
+        buffer.free_space = 6000;
+        assert_eq!(buffer.fill_fraction(), 0.0);
+        assert_eq!(buffer.latency_ms(30_000), 0.0);
+
+        buffer.total_size = 0;
+        assert_eq!(buffer.fill_fraction(), 0.0);
+    }
+
+    #[test]
+    fn test_latency_capped_free() {
+        // 30000 points per second for 64ms = 1920 points of a 6000 point buffer.
+        assert_eq!(
+            BufferState::latency_capped_free(6000, 6000, 30_000, 64),
+            1920
+        );
+        assert_eq!(
+            BufferState::latency_capped_free(6000, 5000, 30_000, 64),
+            920
+        );
+        assert_eq!(BufferState::latency_capped_free(6000, 4000, 30_000, 64), 0);
+
+        // Latencies beyond the buffer size don't limit the free space.
+        let free = BufferState::latency_capped_free(6000, 5000, u32::MAX, u16::MAX);
+        assert_eq!(free, 5000);
+
+        // A zero DAC rate doesn't limit the free space.
+        assert_eq!(BufferState::latency_capped_free(6000, 5000, 0, 64), 5000);
+
+        // Reported free space exceeding the buffer size doesn't overflow.
+        let free = BufferState::latency_capped_free(6000, u16::MAX, 30_000, 64);
+        assert_eq!(free, u16::MAX - 4080);
+        assert_eq!(BufferState::latency_capped_free(0, 100, 30_000, 64), 100);
+    }
+
     #[test]
     fn test_should_send() {
         let mut buffer = BufferState::new();
@@ -187,6 +395,29 @@ mod tests {
         assert_eq!(estimate, 3000); // Should remain unchanged
     }
 
+    #[test]
+    fn test_estimate_current_free_space_with_wrap() {
+        let mut buffer = BufferState::new();
+        buffer.total_size = 6000;
+        buffer.free_space = 3000;
+        buffer.last_update_time = 10_000;
+
+        // Forward steps behave as without a threshold.
+        let estimate = buffer.estimate_current_free_space_with_wrap(11_000, 1000, 100);
+        assert_eq!(estimate, 4000);
+
+        // Jitter: a small backward step counts as no elapsed time.
+        let estimate = buffer.estimate_current_free_space_with_wrap(9_950, 1000, 100);
+        assert_eq!(estimate, 3000);
+
+        // Reset: the counter restarted 500ms ago.
+        let estimate = buffer.estimate_current_free_space_with_wrap(500, 1000, 100);
+        assert_eq!(estimate, 3500);
+
+        // Without a threshold every backward step is jitter.
+        assert_eq!(buffer.estimate_current_free_space(500, 1000), 3000);
+    }
+
     #[test]
     fn test_consume() {
         let mut buffer = BufferState::new();
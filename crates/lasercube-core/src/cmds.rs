@@ -1,9 +1,26 @@
 //! Command definitions for LaserCube protocol.
 
-use crate::{LaserInfo, LaserInfoParseError, Point};
-use std::convert::TryFrom;
+#[cfg(feature = "alloc")]
+use crate::{LaserInfo, Point};
+use crate::{LaserInfoParseError, PointError};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::convert::TryFrom;
 use thiserror::Error;
 
+/// Opcode of the [`CommandType::SetDacRate`] command.
+///
+/// Unconfirmed opcode; adjust if your firmware differs.
+pub const SET_DAC_RATE: u8 = 0x82;
+
+/// Packet sent to the device's [`ALIVE`](crate::port::ALIVE) port to check
+/// that it is reachable.
+///
+/// The packet format of the alive port is undocumented. The device is expected
+/// to reply to any datagram, so this crate sends a single byte and treats any
+/// reply as a sign of life. Adjust here if your firmware expects otherwise.
+pub const ALIVE_PING: [u8; 1] = [0x27];
+
 /// Command types supported by the LaserCube protocol.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[repr(u8)]
@@ -14,12 +31,15 @@ pub enum CommandType {
     EnableBufferSizeResponseOnData = 0x78,
     /// Enable/disable laser output.
     SetOutput = 0x80,
+    /// Set the DAC scan rate in points per second.
+    SetDacRate = SET_DAC_RATE,
     /// Get the number of free samples in the device's ring buffer.
     GetRingbufferEmptySampleCount = 0x8a,
     /// Send point data to render.
     SampleData = 0xa9,
 }
 
+#[cfg(feature = "alloc")]
 /// Command structure for the LaserCube protocol.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Command {
@@ -29,12 +49,15 @@ pub enum Command {
     EnableBufferSizeResponseOnData(bool),
     /// Enable/disable laser output.
     SetOutput(bool),
+    /// Set the DAC scan rate in points per second.
+    SetDacRate(u32),
     /// Get the number of free samples in the device's ring buffer.
     GetRingbufferEmptySampleCount,
     /// Send point data to render.
     SampleData(SampleData),
 }
 
+#[cfg(feature = "alloc")]
 /// Send point data to render.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SampleData {
@@ -46,6 +69,60 @@ pub struct SampleData {
     pub points: Vec<Point>,
 }
 
+#[cfg(feature = "alloc")]
+impl SampleData {
+    /// The size of the header preceding the points: the command byte, a
+    /// padding byte, the message number and the frame number.
+    pub const HEADER_SIZE: usize = 4;
+
+    /// The number of points in this message.
+    pub fn point_count(&self) -> usize {
+        self.points.len()
+    }
+
+    /// The size of this message in bytes once serialized.
+    pub fn serialized_size(&self) -> usize {
+        Self::HEADER_SIZE + self.points.len() * Point::SIZE
+    }
+
+    /// Split this message into messages of at most [`MAX_POINTS_PER_MESSAGE`]
+    /// points each.
+    ///
+    /// Message numbers begin at `message_num_start` and increment (wrapping)
+    /// with each message, while all messages keep this message's `frame_num`.
+    ///
+    /// A message with no points produces no messages.
+    ///
+    /// [`MAX_POINTS_PER_MESSAGE`]: crate::MAX_POINTS_PER_MESSAGE
+    pub fn split(self, message_num_start: u8) -> Vec<SampleData> {
+        crate::frame::chunk_points(&self.points, message_num_start, self.frame_num)
+    }
+
+    /// A message of `count` blank points at the center, parking the galvos
+    /// with the beam off.
+    pub fn idle(message_num: u8, frame_num: u8, count: usize) -> SampleData {
+        SampleData {
+            message_num,
+            frame_num,
+            points: alloc::vec![Point::CENTER_BLANK; count],
+        }
+    }
+}
+
+/// Write each of the commands into the provided byte buffer, back-to-back.
+///
+/// Returns the total number of bytes written.
+///
+/// Whether the device accepts multiple commands within a single datagram is
+/// undocumented. `SampleData` is sent to the DATA port rather than the CMD
+/// port, so it can't share a datagram with the other commands. This is
+/// intended for building sequences of packets, e.g. in tests.
+#[cfg(feature = "alloc")]
+pub fn write_many(commands: &[Command], buffer: &mut Vec<u8>) -> usize {
+    commands.iter().map(|cmd| cmd.write_bytes(buffer)).sum()
+}
+
+#[cfg(feature = "alloc")]
 /// Responses from LaserCube device
 #[derive(Debug, Clone, PartialEq)]
 pub enum Response {
@@ -53,17 +130,60 @@ pub enum Response {
     FullInfo(LaserInfo),
     /// Buffer free space
     BufferFree(u16),
-    /// Simple acknowledgment
-    Ack,
+    /// Buffer free space in response to a data packet, along with the echoed
+    /// message number of the packet.
+    ///
+    /// Replies to data packets without the message number are parsed as
+    /// [`Response::BufferFree`].
+    SampleDataAck { message_num: u8, buffer_free: u16 },
+    /// Simple acknowledgment of the echoed command, along with the payload
+    /// byte following the command byte (e.g. the enable flag for `SetOutput`),
+    /// if present.
+    Ack {
+        command_type: CommandType,
+        value: Option<u8>,
+    },
+    /// A response that could not be parsed, only produced by
+    /// [`Response::try_from_lenient`].
+    ///
+    /// Holds the first byte of the response along with all bytes following it.
+    Unknown { command_byte: u8, payload: Vec<u8> },
+}
+
+/// Error types that can occur when serializing commands
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum SerializeError {
+    #[error("Buffer too small: needed {needed} bytes, only {available} available")]
+    BufferTooSmall { needed: usize, available: usize },
 }
 
+/// Error types that can occur when validating commands before sending
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum CommandValidationError {
+    #[error("Too many points in SampleData: {count} exceeds maximum {max}")]
+    TooManyPoints { count: usize, max: usize },
+    #[error("Invalid point at index {index}: {error}")]
+    InvalidPoint {
+        index: usize,
+        #[source]
+        error: PointError,
+    },
+}
+
+/// Error returned when a byte is not the opcode of any known command type.
+///
+/// Carries the offending byte.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+#[error("Unknown command type: {0:#04x}")]
+pub struct UnknownCommandType(pub u8);
+
 /// Error types that can occur when parsing command responses
 #[derive(Debug, Error)]
 pub enum ResponseParseError {
     #[error("Empty response")]
     EmptyResponse,
-    #[error("Unknown command type: {0}")]
-    UnknownCommandType(u8),
+    #[error(transparent)]
+    UnknownCommandType(#[from] UnknownCommandType),
     #[error("Response too short for {command_type:?} command: expected at least {expected} bytes, got {actual}")]
     ResponseTooShort {
         command_type: CommandType,
@@ -74,6 +194,7 @@ pub enum ResponseParseError {
     LaserInfoError(#[from] LaserInfoParseError),
 }
 
+#[cfg(feature = "alloc")]
 impl TryFrom<&[u8]> for Response {
     type Error = ResponseParseError;
 
@@ -83,10 +204,7 @@ impl TryFrom<&[u8]> for Response {
         }
 
         // First byte is the command type
-        let cmd_type = match CommandType::try_from(bytes[0]) {
-            Ok(cmd) => cmd,
-            Err(_) => return Err(ResponseParseError::UnknownCommandType(bytes[0])),
-        };
+        let cmd_type = CommandType::try_from(bytes[0])?;
 
         match cmd_type {
             CommandType::GetFullInfo => {
@@ -120,33 +238,170 @@ impl TryFrom<&[u8]> for Response {
                     });
                 }
 
-                // The response includes the free buffer space
+                // The response includes the free buffer space, optionally
+                // followed by the echoed message number.
                 let buffer_free = u16::from_le_bytes([bytes[1], bytes[2]]);
-                Ok(Response::BufferFree(buffer_free))
+                match bytes.get(3) {
+                    Some(&message_num) => Ok(Response::SampleDataAck {
+                        message_num,
+                        buffer_free,
+                    }),
+                    None => Ok(Response::BufferFree(buffer_free)),
+                }
             }
 
             // Acknowledgment responses
-            CommandType::EnableBufferSizeResponseOnData | CommandType::SetOutput => {
-                Ok(Response::Ack)
+            CommandType::EnableBufferSizeResponseOnData
+            | CommandType::SetOutput
+            | CommandType::SetDacRate => Ok(Response::Ack {
+                command_type: cmd_type,
+                value: bytes.get(1).copied(),
+            }),
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Response {
+    /// Parse a response, falling back to [`Response::Unknown`] rather than
+    /// erroring if it can't be parsed.
+    ///
+    /// Useful for forward compatibility with firmware sending responses this
+    /// crate doesn't know about, which callers may log and skip.
+    ///
+    /// Only errors if `bytes` is empty.
+    pub fn try_from_lenient(bytes: &[u8]) -> Result<Self, ResponseParseError> {
+        match Response::try_from(bytes) {
+            Err(ResponseParseError::EmptyResponse) => Err(ResponseParseError::EmptyResponse),
+            Err(_) => Ok(Response::Unknown {
+                command_byte: bytes[0],
+                payload: bytes[1..].to_vec(),
+            }),
+            ok => ok,
+        }
+    }
+
+    /// The type of the command echoed in the first byte of the response.
+    ///
+    /// Returns `None` for [`Response::Unknown`] responses whose first byte
+    /// isn't a known command type.
+    ///
+    /// [`Response::BufferFree`] is also parsed from replies to data packets
+    /// that omit the message number, but always reports
+    /// `GetRingbufferEmptySampleCount`. Compare against the first byte of the
+    /// raw response where the two must be told apart.
+    pub fn command_type(&self) -> Option<CommandType> {
+        match self {
+            Response::FullInfo(_) => Some(CommandType::GetFullInfo),
+            Response::BufferFree(_) => Some(CommandType::GetRingbufferEmptySampleCount),
+            Response::SampleDataAck { .. } => Some(CommandType::SampleData),
+            Response::Ack { command_type, .. } => Some(*command_type),
+            Response::Unknown { command_byte, .. } => CommandType::from_opcode(*command_byte),
+        }
+    }
+
+    /// Write this response into the provided byte buffer, as the device would
+    /// send it.
+    ///
+    /// `BufferFree` uses the `GetRingbufferEmptySampleCount` layout, while
+    /// `SampleDataAck` uses the layout of replies to data packets, including
+    /// the message number. Useful for mock devices and emulators.
+    ///
+    /// Returns the number of bytes written.
+    pub fn write_bytes(&self, buffer: &mut Vec<u8>) -> usize {
+        let start_len = buffer.len();
+        match self {
+            Response::FullInfo(info) => {
+                info.write_bytes(buffer);
+            }
+            Response::BufferFree(free) => {
+                let [lo, hi] = free.to_le_bytes();
+                let opcode = CommandType::GetRingbufferEmptySampleCount.opcode();
+                buffer.extend_from_slice(&[opcode, 0x00, lo, hi]);
+            }
+            Response::SampleDataAck {
+                message_num,
+                buffer_free,
+            } => {
+                let [lo, hi] = buffer_free.to_le_bytes();
+                let opcode = CommandType::SampleData.opcode();
+                buffer.extend_from_slice(&[opcode, lo, hi, *message_num]);
             }
+            Response::Ack {
+                command_type,
+                value,
+            } => {
+                buffer.push(command_type.opcode());
+                buffer.extend(value);
+            }
+            Response::Unknown {
+                command_byte,
+                payload,
+            } => {
+                buffer.push(*command_byte);
+                buffer.extend_from_slice(payload);
+            }
+        }
+        buffer.len() - start_len
+    }
+
+    /// Convenience method to get the response bytes as a new `Vec<u8>`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        self.write_bytes(&mut buffer);
+        buffer
+    }
+
+    /// The raw command byte echoed in the first byte of the response.
+    pub fn command_byte(&self) -> u8 {
+        match self {
+            Response::Unknown { command_byte, .. } => *command_byte,
+            res => res.command_type().expect("known response").opcode(),
+        }
+    }
+}
+
+impl CommandType {
+    /// All command types, in order of their opcode.
+    pub const fn all() -> [CommandType; 6] {
+        [
+            CommandType::GetFullInfo,
+            CommandType::EnableBufferSizeResponseOnData,
+            CommandType::SetOutput,
+            CommandType::SetDacRate,
+            CommandType::GetRingbufferEmptySampleCount,
+            CommandType::SampleData,
+        ]
+    }
+
+    /// The opcode sent as the first byte of the command, and echoed as the
+    /// first byte of its response.
+    pub const fn opcode(self) -> u8 {
+        self as u8
+    }
+
+    /// The command type with the given opcode, if any.
+    pub const fn from_opcode(opcode: u8) -> Option<CommandType> {
+        match opcode {
+            0x77 => Some(CommandType::GetFullInfo),
+            0x78 => Some(CommandType::EnableBufferSizeResponseOnData),
+            0x80 => Some(CommandType::SetOutput),
+            SET_DAC_RATE => Some(CommandType::SetDacRate),
+            0x8a => Some(CommandType::GetRingbufferEmptySampleCount),
+            0xa9 => Some(CommandType::SampleData),
+            _ => None,
         }
     }
 }
 
 impl TryFrom<u8> for CommandType {
-    type Error = ();
+    type Error = UnknownCommandType;
     fn try_from(value: u8) -> Result<Self, Self::Error> {
-        match value {
-            0x77 => Ok(CommandType::GetFullInfo),
-            0x78 => Ok(CommandType::EnableBufferSizeResponseOnData),
-            0x80 => Ok(CommandType::SetOutput),
-            0x8a => Ok(CommandType::GetRingbufferEmptySampleCount),
-            0xa9 => Ok(CommandType::SampleData),
-            _ => Err(()),
-        }
+        CommandType::from_opcode(value).ok_or(UnknownCommandType(value))
     }
 }
 
+#[cfg(feature = "alloc")]
 impl Command {
     /// Get the command type associated with this command.
     pub fn command_type(&self) -> CommandType {
@@ -156,72 +411,119 @@ impl Command {
                 CommandType::EnableBufferSizeResponseOnData
             }
             Command::SetOutput(_) => CommandType::SetOutput,
+            Command::SetDacRate(_) => CommandType::SetDacRate,
             Command::GetRingbufferEmptySampleCount => CommandType::GetRingbufferEmptySampleCount,
             Command::SampleData { .. } => CommandType::SampleData,
         }
     }
 
+    /// Check that this command can be sent to the device as is.
+    ///
+    /// A [`SampleData`] message must carry at most
+    /// [`MAX_POINTS_PER_MESSAGE`](crate::MAX_POINTS_PER_MESSAGE) points, all of
+    /// which must be within range. Oversized messages exceed the MTU and are
+    /// silently dropped by the device.
+    pub fn validate(&self) -> Result<(), CommandValidationError> {
+        let Command::SampleData(data) = self else {
+            return Ok(());
+        };
+        if data.points.len() > crate::MAX_POINTS_PER_MESSAGE {
+            return Err(CommandValidationError::TooManyPoints {
+                count: data.points.len(),
+                max: crate::MAX_POINTS_PER_MESSAGE,
+            });
+        }
+        for (index, p) in data.points.iter().enumerate() {
+            Point::try_new(p.pos, p.rgb)
+                .map_err(|error| CommandValidationError::InvalidPoint { index, error })?;
+        }
+        Ok(())
+    }
+
     /// Estimate the size in bytes this command will take when serialized.
     pub fn size(&self) -> usize {
         match self {
             Command::GetFullInfo => 1,
             Command::EnableBufferSizeResponseOnData(_) => 2,
             Command::SetOutput(_) => 2,
+            Command::SetDacRate(_) => 5,
             Command::GetRingbufferEmptySampleCount => 1,
-            Command::SampleData(SampleData { points, .. }) => {
-                // 1 byte command
-                // + 1 byte padding
-                // + 1 byte message num
-                // + 1 byte frame num
-                4 + (points.len() * 10) // Each point is 10 bytes
-            }
+            Command::SampleData(sample_data) => sample_data.serialized_size(),
         }
     }
 
     /// Write this command into the provided byte buffer.
     ///
     /// Returns the number of bytes written.
+    ///
+    /// A [`SampleData`] message carrying more than
+    /// [`MAX_POINTS_PER_MESSAGE`](crate::MAX_POINTS_PER_MESSAGE) points will
+    /// be dropped by the device. Debug builds panic on such messages; use
+    /// [`SampleData::split`] to divide them first.
     pub fn write_bytes(&self, buffer: &mut Vec<u8>) -> usize {
+        if let Command::SampleData(data) = self {
+            debug_assert!(
+                data.points.len() <= crate::MAX_POINTS_PER_MESSAGE,
+                "SampleData with {} points exceeds MAX_POINTS_PER_MESSAGE ({})",
+                data.points.len(),
+                crate::MAX_POINTS_PER_MESSAGE,
+            );
+        }
         let start_len = buffer.len();
+        buffer.resize(start_len + self.size(), 0);
+        self.write_to_slice(&mut buffer[start_len..])
+            .expect("buffer was resized to fit the command")
+    }
 
+    /// Write this command into the start of the provided slice.
+    ///
+    /// Unlike [`Command::write_bytes`], this never allocates.
+    ///
+    /// Produces output identical to [`Command::write_bytes`].
+    ///
+    /// Returns the number of bytes written, or an error if the slice is
+    /// smaller than [`Command::size`].
+    pub fn write_to_slice(&self, buffer: &mut [u8]) -> Result<usize, SerializeError> {
+        let size = self.size();
+        let available = buffer.len();
+        let buffer = buffer
+            .get_mut(..size)
+            .ok_or(SerializeError::BufferTooSmall {
+                needed: size,
+                available,
+            })?;
+
+        buffer[0] = self.command_type().opcode();
         match self {
-            Command::GetFullInfo => {
-                buffer.push(CommandType::GetFullInfo as u8);
-            }
+            Command::GetFullInfo | Command::GetRingbufferEmptySampleCount => {}
 
-            Command::EnableBufferSizeResponseOnData(enable) => {
-                buffer.push(CommandType::EnableBufferSizeResponseOnData as u8);
-                buffer.push(if *enable { 1 } else { 0 });
+            Command::EnableBufferSizeResponseOnData(enable) | Command::SetOutput(enable) => {
+                buffer[1] = if *enable { 1 } else { 0 };
             }
 
-            Command::SetOutput(enable) => {
-                buffer.push(CommandType::SetOutput as u8);
-                buffer.push(if *enable { 1 } else { 0 });
-            }
-
-            Command::GetRingbufferEmptySampleCount => {
-                buffer.push(CommandType::GetRingbufferEmptySampleCount as u8);
+            Command::SetDacRate(rate) => {
+                buffer[1..5].copy_from_slice(&rate.to_le_bytes());
             }
 
             Command::SampleData(data) => {
                 // Header: command byte, 0x00, message_num, frame_num
-                buffer.push(CommandType::SampleData as u8);
-                buffer.push(0x00); // Always 0x00 according to protocol
-                buffer.push(data.message_num);
-                buffer.push(data.frame_num);
+                buffer[1] = 0x00; // Always 0x00 according to protocol
+                buffer[2] = data.message_num;
+                buffer[3] = data.frame_num;
 
-                // Append each point's serialized bytes
-                for point in &data.points {
+                // Write each point's serialized bytes
+                let point_chunks = buffer[4..].chunks_exact_mut(Point::SIZE);
+                for (chunk, point) in point_chunks.zip(&data.points) {
                     let point_bytes: [u8; Point::SIZE] = (*point).into();
-                    buffer.extend_from_slice(&point_bytes);
+                    chunk.copy_from_slice(&point_bytes);
                 }
             }
         }
 
-        buffer.len() - start_len
+        Ok(size)
     }
 
-    /// Convenience method to get command bytes as a new Vec<u8>
+    /// Convenience method to get command bytes as a new `Vec<u8>`
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut buffer = Vec::with_capacity(self.size());
         self.write_bytes(&mut buffer);
@@ -229,9 +531,226 @@ impl Command {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     use super::*;
+    use crate::LaserInfoHeader;
+
+    fn test_commands() -> Vec<Command> {
+        let points = (0..3)
+            .map(|i| Point::new([0x100 * i, 0x800], [0xFFF, 0, 0x123 * i]))
+            .collect();
+        vec![
+            Command::GetFullInfo,
+            Command::EnableBufferSizeResponseOnData(true),
+            Command::SetOutput(false),
+            Command::SetDacRate(30_000),
+            Command::GetRingbufferEmptySampleCount,
+            Command::SampleData(SampleData {
+                message_num: 12,
+                frame_num: 34,
+                points,
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_write_to_slice_exact() {
+        for command in test_commands() {
+            let mut buffer = vec![0xAA; command.size()];
+            let len = command.write_to_slice(&mut buffer).unwrap();
+            assert_eq!(len, command.size());
+            assert_eq!(buffer, command.to_bytes());
+        }
+    }
+
+    #[test]
+    fn test_write_to_slice_oversized() {
+        for command in test_commands() {
+            let mut buffer = [0xAA; 128];
+            let len = command.write_to_slice(&mut buffer).unwrap();
+            assert_eq!(&buffer[..len], &command.to_bytes()[..]);
+            // Bytes beyond the command are untouched.
+            assert!(buffer[len..].iter().all(|&b| b == 0xAA));
+        }
+    }
+
+    #[test]
+    fn test_write_to_slice_too_small() {
+        for command in test_commands() {
+            let size = command.size();
+            let mut buffer = vec![0xAA; size - 1];
+            let result = command.write_to_slice(&mut buffer);
+            assert_eq!(
+                result,
+                Err(SerializeError::BufferTooSmall {
+                    needed: size,
+                    available: size - 1,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_sample_data_size() {
+        let data = SampleData::idle(0, 0, 10);
+        assert_eq!(data.point_count(), 10);
+        assert_eq!(data.serialized_size(), 104);
+        let command = Command::SampleData(data);
+        assert_eq!(command.size(), 104);
+        assert_eq!(command.to_bytes().len(), 104);
+        assert_eq!(SampleData::idle(0, 0, 0).serialized_size(), 4);
+    }
+
+    #[test]
+    fn test_sample_data_split() {
+        let points: Vec<Point> = (0..300)
+            .map(|i| Point::new([i as u16, 0], [0xFFF; 3]))
+            .collect();
+        let data = SampleData {
+            message_num: 0,
+            frame_num: 5,
+            points: points.clone(),
+        };
+        let messages = data.split(255);
+        let lens: Vec<usize> = messages.iter().map(|m| m.points.len()).collect();
+        assert_eq!(lens, [140, 140, 20]);
+        let message_nums: Vec<u8> = messages.iter().map(|m| m.message_num).collect();
+        assert_eq!(message_nums, [255, 0, 1]);
+        assert!(messages.iter().all(|m| m.frame_num == 5));
+        let joined: Vec<Point> = messages.into_iter().flat_map(|m| m.points).collect();
+        assert_eq!(joined, points);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "exceeds MAX_POINTS_PER_MESSAGE")]
+    fn test_write_bytes_oversized_sample_data() {
+        let command = Command::SampleData(SampleData {
+            message_num: 0,
+            frame_num: 0,
+            points: vec![Point::new([0; 2], [0; 3]); crate::MAX_POINTS_PER_MESSAGE + 1],
+        });
+        command.to_bytes();
+    }
+
+    #[test]
+    fn test_validate() {
+        for command in test_commands() {
+            assert_eq!(command.validate(), Ok(()));
+        }
+        let full = Command::SampleData(SampleData {
+            message_num: 0,
+            frame_num: 0,
+            points: vec![Point::CENTER_BLANK; crate::MAX_POINTS_PER_MESSAGE],
+        });
+        assert_eq!(full.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_idle() {
+        let idle = SampleData::idle(3, 4, crate::MAX_POINTS_PER_MESSAGE);
+        assert_eq!((idle.message_num, idle.frame_num), (3, 4));
+        assert_eq!(idle.points.len(), crate::MAX_POINTS_PER_MESSAGE);
+        assert!(idle
+            .points
+            .iter()
+            .all(|p| p.is_blank() && p.pos == Point::CENTER_POS));
+        assert!(SampleData::idle(0, 0, 0).points.is_empty());
+    }
+
+    #[test]
+    fn test_max_message_size() {
+        let command = Command::SampleData(SampleData {
+            message_num: 0,
+            frame_num: 0,
+            points: vec![Point::CENTER_BLANK; crate::MAX_POINTS_PER_MESSAGE],
+        });
+        assert_eq!(crate::MAX_MESSAGE_SIZE, 1404);
+        assert_eq!(command.size(), crate::MAX_MESSAGE_SIZE);
+        assert_eq!(command.to_bytes().len(), crate::MAX_MESSAGE_SIZE);
+    }
+
+    #[test]
+    fn test_validate_too_many_points() {
+        let count = crate::MAX_POINTS_PER_MESSAGE + 1;
+        let command = Command::SampleData(SampleData {
+            message_num: 0,
+            frame_num: 0,
+            points: vec![Point::CENTER_BLANK; count],
+        });
+        assert_eq!(
+            command.validate(),
+            Err(CommandValidationError::TooManyPoints {
+                count,
+                max: crate::MAX_POINTS_PER_MESSAGE,
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_invalid_point() {
+        let command = Command::SampleData(SampleData {
+            message_num: 0,
+            frame_num: 0,
+            points: vec![Point::CENTER_BLANK, Point::new([0, 0x1000], [0; 3])],
+        });
+        assert_eq!(
+            command.validate(),
+            Err(CommandValidationError::InvalidPoint {
+                index: 1,
+                error: PointError::Y(0x1000),
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_dac_rate_bytes() {
+        let command = Command::SetDacRate(0x0001_D4C0);
+        assert_eq!(command.command_type(), CommandType::SetDacRate);
+        assert_eq!(command.size(), 5);
+        assert_eq!(command.to_bytes(), [SET_DAC_RATE, 0xC0, 0xD4, 0x01, 0x00]);
+        assert_eq!(
+            Command::SetDacRate(u32::MAX).to_bytes(),
+            [SET_DAC_RATE, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+    }
+
+    #[test]
+    fn test_parse_set_dac_rate_ack() {
+        let response = Response::try_from(&[SET_DAC_RATE][..]).unwrap();
+        assert_eq!(
+            response,
+            Response::Ack {
+                command_type: CommandType::SetDacRate,
+                value: None,
+            }
+        );
+        assert_eq!(
+            CommandType::try_from(SET_DAC_RATE),
+            Ok(CommandType::SetDacRate)
+        );
+    }
+
+    #[test]
+    fn test_write_many() {
+        let commands = test_commands();
+        let mut buffer = vec![0xFF];
+        let len = write_many(&commands, &mut buffer);
+        let expected: Vec<u8> = commands.iter().flat_map(|c| c.to_bytes()).collect();
+        assert_eq!(len, expected.len());
+        assert_eq!(buffer[0], 0xFF);
+        assert_eq!(buffer[1..], expected);
+        assert_eq!(write_many(&[], &mut buffer), 0);
+    }
+
+    #[test]
+    fn test_write_bytes_appends() {
+        let command = Command::SetOutput(true);
+        let mut buffer = vec![0xFF];
+        assert_eq!(command.write_bytes(&mut buffer), 2);
+        assert_eq!(buffer, [0xFF, CommandType::SetOutput as u8, 1]);
+    }
 
     #[test]
     fn test_parse_buffer_free_response() {
@@ -246,6 +765,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_sample_data_response() {
+        // Short layout with only the buffer free space
+        let response = [0xa9, 0xe8, 0x03];
+        let parsed = Response::try_from(&response[..]).unwrap();
+        assert_eq!(parsed, Response::BufferFree(1000));
+
+        // Extended layout with the echoed message number
+        let response = [0xa9, 0xe8, 0x03, 42];
+        let parsed = Response::try_from(&response[..]).unwrap();
+        assert_eq!(
+            parsed,
+            Response::SampleDataAck {
+                message_num: 42,
+                buffer_free: 1000,
+            }
+        );
+
+        // Too short for either layout
+        let result = Response::try_from(&[0xa9, 0xe8][..]);
+        assert!(matches!(
+            result,
+            Err(ResponseParseError::ResponseTooShort {
+                command_type: CommandType::SampleData,
+                ..
+            })
+        ));
+    }
+
     #[test]
     fn test_parse_ack_response() {
         // Sample response for SetOutput
@@ -254,9 +802,44 @@ mod tests {
         let parsed = Response::try_from(&response[..]).unwrap();
 
         match parsed {
-            Response::Ack => {}
+            Response::Ack {
+                command_type: CommandType::SetOutput,
+                value: None,
+            } => {}
             _ => panic!("Wrong response type parsed"),
         }
+
+        // Response echoing the enable flag
+        let response = [0x80, 0x01];
+        let parsed = Response::try_from(&response[..]).unwrap();
+        assert_eq!(
+            parsed,
+            Response::Ack {
+                command_type: CommandType::SetOutput,
+                value: Some(0x01),
+            }
+        );
+    }
+
+    #[test]
+    fn test_response_command_type() {
+        let info = LaserInfo {
+            header: LaserInfoHeader::from([0; LaserInfoHeader::SIZE]),
+            model_name: "LaserCube".into(),
+        };
+        let responses = [
+            info.to_bytes(),
+            vec![0x78],
+            vec![0x80, 0x01],
+            vec![SET_DAC_RATE],
+            vec![0x8a, 0x00, 0xe8, 0x03],
+            vec![0xa9, 0xe8, 0x03, 42],
+        ];
+        for bytes in responses {
+            let response = Response::try_from(&bytes[..]).unwrap();
+            assert_eq!(response.command_byte(), bytes[0], "{response:?}");
+            assert_eq!(response.command_type().map(|t| t as u8), Some(bytes[0]));
+        }
     }
 
     #[test]
@@ -269,7 +852,9 @@ mod tests {
         let result = Response::try_from(&[0xFF][..]);
         assert!(matches!(
             result,
-            Err(ResponseParseError::UnknownCommandType(0xFF))
+            Err(ResponseParseError::UnknownCommandType(UnknownCommandType(
+                0xFF
+            )))
         ));
 
         // Response too short
@@ -282,4 +867,111 @@ mod tests {
             })
         ));
     }
+
+    #[test]
+    fn test_parse_lenient() {
+        // Unknown command type, with the payload preserved.
+        let result = Response::try_from_lenient(&[0xFF, 0x01, 0x02, 0x03]).unwrap();
+        assert_eq!(
+            result,
+            Response::Unknown {
+                command_byte: 0xFF,
+                payload: vec![0x01, 0x02, 0x03],
+            }
+        );
+        assert_eq!(result.command_type(), None);
+        assert_eq!(result.command_byte(), 0xFF);
+
+        // Malformed response to a known command.
+        let result = Response::try_from_lenient(&[0x8a, 0x00]).unwrap();
+        assert_eq!(
+            result.command_type(),
+            Some(CommandType::GetRingbufferEmptySampleCount)
+        );
+        assert!(matches!(result, Response::Unknown { payload, .. } if payload == [0x00]));
+
+        // Well-formed responses parse as usual.
+        let result = Response::try_from_lenient(&[0x8a, 0x00, 0xe8, 0x03]).unwrap();
+        assert_eq!(result, Response::BufferFree(1000));
+
+        let result = Response::try_from_lenient(&[]);
+        assert!(matches!(result, Err(ResponseParseError::EmptyResponse)));
+    }
+
+    #[test]
+    fn test_opcodes() {
+        let opcodes: Vec<u8> = CommandType::all().map(CommandType::opcode).to_vec();
+        assert_eq!(opcodes, [0x77, 0x78, 0x80, SET_DAC_RATE, 0x8a, 0xa9]);
+        for command_type in CommandType::all() {
+            let opcode = command_type.opcode();
+            assert_eq!(opcode, command_type as u8);
+            assert_eq!(CommandType::from_opcode(opcode), Some(command_type));
+            assert_eq!(CommandType::try_from(opcode), Ok(command_type));
+        }
+        let unknown = (0..=u8::MAX).filter(|&b| CommandType::from_opcode(b).is_none());
+        assert_eq!(unknown.count(), 256 - CommandType::all().len());
+    }
+
+    #[test]
+    fn test_unknown_command_type_error() {
+        let err = CommandType::try_from(0x42).unwrap_err();
+        assert_eq!(err, UnknownCommandType(0x42));
+        assert_eq!(err.to_string(), "Unknown command type: 0x42");
+
+        let err = Response::try_from(&[0x42, 0x00][..]).unwrap_err();
+        assert!(matches!(
+            err,
+            ResponseParseError::UnknownCommandType(UnknownCommandType(0x42))
+        ));
+        assert_eq!(err.to_string(), "Unknown command type: 0x42");
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let info = LaserInfo {
+            header: LaserInfoHeader {
+                rx_buffer_free: 1234,
+                serial_number: [1, 2, 3, 4, 5, 6],
+                ..Default::default()
+            },
+            model_name: "LaserCube".into(),
+        };
+        let responses = [
+            Response::FullInfo(info),
+            Response::BufferFree(1000),
+            Response::SampleDataAck {
+                message_num: 7,
+                buffer_free: 0xFFFF,
+            },
+            Response::Ack {
+                command_type: CommandType::SetOutput,
+                value: Some(1),
+            },
+            Response::Ack {
+                command_type: CommandType::EnableBufferSizeResponseOnData,
+                value: None,
+            },
+            Response::Ack {
+                command_type: CommandType::SetDacRate,
+                value: Some(0),
+            },
+        ];
+        for response in responses {
+            let bytes = response.to_bytes();
+            assert_eq!(bytes[0], response.command_byte());
+            assert_eq!(Response::try_from(&bytes[..]).unwrap(), response);
+        }
+        assert_eq!(
+            Response::BufferFree(1000).to_bytes(),
+            [0x8a, 0x00, 0xe8, 0x03]
+        );
+
+        let unknown = Response::Unknown {
+            command_byte: 0xFF,
+            payload: vec![1, 2, 3],
+        };
+        let bytes = unknown.to_bytes();
+        assert_eq!(bytes, [0xFF, 1, 2, 3]);
+        assert_eq!(Response::try_from_lenient(&bytes).unwrap(), unknown);
+    }
 }
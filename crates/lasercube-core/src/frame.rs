@@ -0,0 +1,284 @@
+//! Helpers for splitting frames of points into data messages.
+
+use crate::{Point, SampleData, MAX_POINTS_PER_MESSAGE};
+use alloc::vec::Vec;
+
+/// Split a frame of points into `SampleData` messages.
+///
+/// Each message contains at most `MAX_POINTS_PER_MESSAGE` points. Message
+/// numbers begin at `message_num_start` and increment (wrapping) with each
+/// message, while all messages share the given `frame_num`.
+///
+/// An empty slice of points produces no messages.
+pub fn chunk_points(points: &[Point], message_num_start: u8, frame_num: u8) -> Vec<SampleData> {
+    points
+        .chunks(MAX_POINTS_PER_MESSAGE)
+        .enumerate()
+        .map(|(i, chunk)| SampleData {
+            message_num: message_num_start.wrapping_add(i as u8),
+            frame_num,
+            points: chunk.to_vec(),
+        })
+        .collect()
+}
+
+/// Extension trait for lazily chunking an iterator of points into
+/// `SampleData` messages.
+pub trait PointChunks: Iterator<Item = Point> + Sized {
+    /// Lazily group points into `SampleData` messages.
+    ///
+    /// Each message contains at most `MAX_POINTS_PER_MESSAGE` points. Message
+    /// numbers begin at `0` and increment (wrapping) with each message, while
+    /// all messages share the given `frame_num`.
+    ///
+    /// Only a single message worth of points is buffered at a time.
+    fn sample_data_chunks(self, frame_num: u8) -> SampleDataChunks<Self> {
+        SampleDataChunks {
+            points: self,
+            message_num: 0,
+            frame_num,
+        }
+    }
+}
+
+impl<I> PointChunks for I where I: Iterator<Item = Point> {}
+
+/// Iterator yielding `SampleData` messages, produced by
+/// [`PointChunks::sample_data_chunks`].
+#[derive(Clone, Debug)]
+pub struct SampleDataChunks<I> {
+    points: I,
+    message_num: u8,
+    frame_num: u8,
+}
+
+impl<I> Iterator for SampleDataChunks<I>
+where
+    I: Iterator<Item = Point>,
+{
+    type Item = SampleData;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let points: Vec<Point> = self.points.by_ref().take(MAX_POINTS_PER_MESSAGE).collect();
+        if points.is_empty() {
+            return None;
+        }
+        let message_num = self.message_num;
+        self.message_num = message_num.wrapping_add(1);
+        Some(SampleData {
+            message_num,
+            frame_num: self.frame_num,
+            points,
+        })
+    }
+}
+
+/// Produces `SampleData` messages with incrementing message numbers.
+///
+/// Message numbers wrap from `255` back to `0`. All messages share the current
+/// frame number until [`MessageCounter::next_frame`] is called.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MessageCounter {
+    /// The message number of the next message.
+    pub message_num: u8,
+    /// The frame number of the next message.
+    pub frame_num: u8,
+}
+
+impl MessageCounter {
+    /// Create a counter starting at the given message and frame numbers.
+    pub const fn new(message_num: u8, frame_num: u8) -> Self {
+        Self {
+            message_num,
+            frame_num,
+        }
+    }
+
+    /// Wrap the given points in a message and advance the message number.
+    ///
+    /// The points are not checked against `MAX_POINTS_PER_MESSAGE`.
+    pub fn next(&mut self, points: Vec<Point>) -> SampleData {
+        let message_num = self.message_num;
+        self.message_num = message_num.wrapping_add(1);
+        SampleData {
+            message_num,
+            frame_num: self.frame_num,
+            points,
+        }
+    }
+
+    /// Advance the frame number (wrapping) for subsequent messages.
+    pub fn next_frame(&mut self) {
+        self.frame_num = self.frame_num.wrapping_add(1);
+    }
+}
+
+/// Iterator sequencing complete frames of points into `SampleData` messages.
+///
+/// Each frame is split into messages of at most `MAX_POINTS_PER_MESSAGE`
+/// points. Message numbers increment (wrapping) with each message, while the
+/// frame number increments (wrapping) once per frame, so that all messages of
+/// a frame share its frame number. An empty frame produces no messages, but
+/// still consumes a frame number.
+#[derive(Clone, Debug)]
+pub struct FrameSequencer<I> {
+    frames: I,
+    counter: MessageCounter,
+    pending: alloc::vec::IntoIter<SampleData>,
+}
+
+impl<I> FrameSequencer<I>
+where
+    I: Iterator<Item = Vec<Point>>,
+{
+    /// Sequence the given frames, starting from message and frame number `0`.
+    pub fn new(frames: impl IntoIterator<IntoIter = I>) -> Self {
+        Self::with_counter(frames, MessageCounter::default())
+    }
+
+    /// Sequence the given frames, starting from the counter's message and
+    /// frame numbers.
+    pub fn with_counter(frames: impl IntoIterator<IntoIter = I>, counter: MessageCounter) -> Self {
+        Self {
+            frames: frames.into_iter(),
+            counter,
+            pending: Vec::new().into_iter(),
+        }
+    }
+
+    /// The message and frame numbers of the next frame to be sequenced.
+    pub fn counter(&self) -> MessageCounter {
+        self.counter
+    }
+}
+
+impl<I> Iterator for FrameSequencer<I>
+where
+    I: Iterator<Item = Vec<Point>>,
+{
+    type Item = SampleData;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(data) = self.pending.next() {
+                return Some(data);
+            }
+            let frame = self.frames.next()?;
+            let MessageCounter {
+                message_num,
+                frame_num,
+            } = self.counter;
+            let messages = chunk_points(&frame, message_num, frame_num);
+            self.counter.message_num = message_num.wrapping_add(messages.len() as u8);
+            self.counter.next_frame();
+            self.pending = messages.into_iter();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn points(n: usize) -> Vec<Point> {
+        (0..n)
+            .map(|i| Point::new([i as u16; 2], [0xFFF; 3]))
+            .collect()
+    }
+
+    #[test]
+    fn test_chunk_points_empty() {
+        assert!(chunk_points(&[], 0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_chunk_points_exact() {
+        let chunks = chunk_points(&points(MAX_POINTS_PER_MESSAGE), 3, 7);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].message_num, 3);
+        assert_eq!(chunks[0].frame_num, 7);
+    }
+
+    #[test]
+    fn test_chunk_points() {
+        let points = points(MAX_POINTS_PER_MESSAGE * 3 + 1);
+        let chunks = chunk_points(&points, 254, 9);
+        assert_eq!(chunks.len(), 4);
+        let message_nums: Vec<u8> = chunks.iter().map(|c| c.message_num).collect();
+        assert_eq!(message_nums, [254, 255, 0, 1]);
+        assert!(chunks.iter().all(|c| c.frame_num == 9));
+        assert!(chunks
+            .iter()
+            .all(|c| c.points.len() <= MAX_POINTS_PER_MESSAGE));
+        let joined: Vec<Point> = chunks.into_iter().flat_map(|c| c.points).collect();
+        assert_eq!(joined, points);
+    }
+
+    #[test]
+    fn test_sample_data_chunks() {
+        let points = points(350);
+        let chunks: Vec<SampleData> = points.iter().copied().sample_data_chunks(4).collect();
+        let lens: Vec<usize> = chunks.iter().map(|c| c.points.len()).collect();
+        assert_eq!(lens, [140, 140, 70]);
+        let message_nums: Vec<u8> = chunks.iter().map(|c| c.message_num).collect();
+        assert_eq!(message_nums, [0, 1, 2]);
+        assert!(chunks.iter().all(|c| c.frame_num == 4));
+        assert_eq!(chunks, chunk_points(&points, 0, 4));
+    }
+
+    #[test]
+    fn test_sample_data_chunks_wrapping() {
+        let chunks = core::iter::repeat(Point::CENTER_BLANK)
+            .sample_data_chunks(0)
+            .skip(255);
+        let message_nums: Vec<u8> = chunks.take(3).map(|c| c.message_num).collect();
+        assert_eq!(message_nums, [255, 0, 1]);
+        assert!(core::iter::empty().sample_data_chunks(0).next().is_none());
+    }
+
+    #[test]
+    fn test_message_counter() {
+        let mut counter = MessageCounter::default();
+        for i in 0..300usize {
+            let data = counter.next(points(1));
+            assert_eq!(data.message_num, (i % 256) as u8);
+            assert_eq!(data.frame_num, 0);
+        }
+        assert_eq!(counter.message_num, 300u16 as u8);
+
+        counter.next_frame();
+        let data = counter.next(Vec::new());
+        assert_eq!((data.message_num, data.frame_num), (44, 1));
+
+        let mut counter = MessageCounter::new(0, 255);
+        counter.next_frame();
+        assert_eq!(counter.frame_num, 0);
+    }
+
+    #[test]
+    fn test_frame_sequencer() {
+        let frames = vec![points(200), points(200)];
+        let messages: Vec<SampleData> = FrameSequencer::new(frames).collect();
+        let nums: Vec<(u8, u8)> = messages
+            .iter()
+            .map(|m| (m.message_num, m.frame_num))
+            .collect();
+        assert_eq!(nums, [(0, 0), (1, 0), (2, 1), (3, 1)]);
+        let lens: Vec<usize> = messages.iter().map(|m| m.points.len()).collect();
+        assert_eq!(lens, [140, 60, 140, 60]);
+
+        // Empty frames produce no messages, but consume a frame number.
+        let frames = vec![points(1), Vec::new(), points(1)];
+        let frame_nums: Vec<u8> = FrameSequencer::new(frames).map(|m| m.frame_num).collect();
+        assert_eq!(frame_nums, [0, 2]);
+
+        let counter = MessageCounter::new(255, 255);
+        let mut sequencer = FrameSequencer::with_counter(vec![points(200), points(1)], counter);
+        let nums: Vec<(u8, u8)> = sequencer
+            .by_ref()
+            .map(|m| (m.message_num, m.frame_num))
+            .collect();
+        assert_eq!(nums, [(255, 255), (0, 255), (1, 0)]);
+        assert_eq!(sequencer.counter(), MessageCounter::new(2, 1));
+    }
+}
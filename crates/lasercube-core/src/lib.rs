@@ -2,18 +2,44 @@
 //!
 //! This crate provides the fundamental data structures and protocol definitions
 //! for communicating with LaserCube devices, without any actual network implementation.
+//!
+//! ## Features
+//!
+//! - `std` (default): Enables `alloc` along with helpers requiring `std`, such
+//!   as [`ColorProfile`] and the [`recording`] module.
+//! - `alloc`: Enables types requiring an allocator, including [`Command`],
+//!   [`LaserInfo`] and the [`frame`] module. Without `alloc`, the crate is
+//!   `no_std` and provides [`Point`], [`StatusFlags`], [`LaserInfoHeader`]
+//!   and [`BufferState`].
+//! - `serde`: Derives `Serialize` and `Deserialize` for the core types.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
 
 pub mod buffer;
 pub mod cmds;
+#[cfg(feature = "alloc")]
+pub mod frame;
 pub mod point;
+#[cfg(feature = "std")]
+pub mod recording;
 pub mod status;
+pub mod timing;
 
 // Re-export commonly used types
+#[cfg(feature = "alloc")]
+use alloc::{format, string::String, vec::Vec};
 pub use buffer::BufferState;
-pub use cmds::{Command, CommandType, SampleData};
-pub use point::Point;
-pub use status::StatusFlags;
-use std::{convert::TryFrom, ffi::CStr, net::Ipv4Addr};
+pub use cmds::CommandType;
+#[cfg(feature = "alloc")]
+pub use cmds::{Command, SampleData};
+use core::net::Ipv4Addr;
+#[cfg(feature = "std")]
+pub use point::ColorProfile;
+pub use point::{ColorMask, DecodeError, OutOfRange, Point, PointError};
+pub use status::{DecodedStatus, StatusFlags};
 use thiserror::Error;
 
 /// Ports that the device listens on.
@@ -26,17 +52,62 @@ pub mod port {
     pub const DATA: u16 = 45458;
 }
 
+/// The ports used to communicate with a device.
+///
+/// Defaults to the standard ports in [`port`]. Override these to reach
+/// devices behind a proxy that remaps ports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ports {
+    /// Port for "alive" messages (simple pings).
+    pub alive: u16,
+    /// Port for commands (get info, enable/disable output, etc.).
+    pub cmd: u16,
+    /// Port for point data transmission.
+    pub data: u16,
+}
+
+impl Ports {
+    /// The standard ports in [`port`].
+    pub const DEFAULT: Self = Self {
+        alive: port::ALIVE,
+        cmd: port::CMD,
+        data: port::DATA,
+    };
+}
+
+impl Default for Ports {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
 /// Maximum points per data message to stay under typical network MTU.
 pub const MAX_POINTS_PER_MESSAGE: usize = 140;
 
+/// Size in bytes of a `SampleData` message carrying `MAX_POINTS_PER_MESSAGE`
+/// points: a 4 byte header followed by `Point::SIZE` bytes per point.
+///
+/// A typical Ethernet MTU of 1500 bytes leaves 1472 bytes of UDP payload after
+/// the 20 byte IPv4 and 8 byte UDP headers. Messages larger than this would be
+/// fragmented, which the device does not handle.
+pub const MAX_MESSAGE_SIZE: usize = 4 + MAX_POINTS_PER_MESSAGE * Point::SIZE;
+
+const _: () = assert!(
+    MAX_MESSAGE_SIZE <= 1472,
+    "SampleData messages must fit in one datagram"
+);
+
 /// Default broadcast address
 pub const DEFAULT_BROADCAST_ADDR: &str = "255.255.255.255";
 
 /// Connection type for the LaserCube.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[repr(u8)]
 pub enum ConnectionType {
     /// Unknown connection type.
+    #[default]
     Unknown = 0,
     /// Connected via USB.
     Usb = 1,
@@ -46,17 +117,56 @@ pub enum ConnectionType {
     Wifi = 3,
 }
 
+/// The LaserCube model, derived from [`LaserInfoHeader::model_number`].
+///
+/// The mapping of model numbers to models is unconfirmed; adjust it if your
+/// devices report differently. Unrecognized model numbers are preserved as
+/// [`Model::Unknown`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Model {
+    /// The original LaserCube (model number `1`).
+    LaserCube,
+    /// The LaserCube Pro (model number `2`).
+    LaserCubePro,
+    /// A model number without a known mapping.
+    Unknown(u8),
+}
+
+/// A firmware version, ordered by major then minor version.
+///
+/// # Example
+///
+/// ```
+/// use lasercube_core::FirmwareVersion;
+///
+/// let version = FirmwareVersion { major: 0, minor: 12 };
+/// assert!(version < FirmwareVersion::V0_13);
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FirmwareVersion {
+    /// The major version.
+    pub major: u8,
+    /// The minor version.
+    pub minor: u8,
+}
+
 /// Error types that can occur when parsing a LaserInfo response
 #[derive(Debug, Error)]
 pub enum LaserInfoParseError {
     #[error("Response too short: expected at least {expected} bytes, got {actual}")]
     ResponseTooShort { expected: usize, actual: usize },
+    /// No longer produced: model names filling the field without a null
+    /// terminator are accepted.
+    #[deprecated(note = "model names without a null terminator are accepted")]
     #[error("Missing null terminator in model name: {0}")]
-    MissingNullTerminator(#[from] std::ffi::FromBytesUntilNulError),
+    MissingNullTerminator(#[from] core::ffi::FromBytesUntilNulError),
 }
 
 /// Fixed-size header portion of the LaserInfo response
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LaserInfoHeader {
     /// Firmware major version
     pub fw_major: u8,
@@ -86,8 +196,24 @@ pub struct LaserInfoHeader {
     pub ip_addr: Ipv4Addr,
 }
 
+/// Safety-relevant device state, resolved for the device's firmware version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SafetyState {
+    /// Whether laser output is enabled.
+    pub output_enabled: bool,
+    /// Whether the interlock is enabled.
+    pub interlock_enabled: bool,
+    /// Whether a temperature warning is active.
+    pub temperature_warning: bool,
+    /// Whether an over-temperature condition is active.
+    pub over_temperature: bool,
+}
+
 /// The fixed-size header along with the variable length model name.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LaserInfo {
     /// Fixed-size header fields
     pub header: LaserInfoHeader,
@@ -95,27 +221,192 @@ pub struct LaserInfo {
     pub model_name: String,
 }
 
+impl ConnectionType {
+    /// All connection types, in order of their encoded value.
+    pub const fn all() -> [ConnectionType; 4] {
+        [
+            ConnectionType::Unknown,
+            ConnectionType::Usb,
+            ConnectionType::Ethernet,
+            ConnectionType::Wifi,
+        ]
+    }
+
+    /// The byte used to encode the connection type.
+    pub const fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    /// A lowercase label for the connection type, e.g. `"wifi"`.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionType::Unknown => "unknown",
+            ConnectionType::Usb => "usb",
+            ConnectionType::Ethernet => "ethernet",
+            ConnectionType::Wifi => "wifi",
+        }
+    }
+}
+
+impl Model {
+    /// The model number identifying this model.
+    pub const fn to_u8(self) -> u8 {
+        match self {
+            Model::LaserCube => 1,
+            Model::LaserCubePro => 2,
+            Model::Unknown(n) => n,
+        }
+    }
+
+    /// A human readable name for the model, e.g. `"LaserCube Pro"`.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Model::LaserCube => "LaserCube",
+            Model::LaserCubePro => "LaserCube Pro",
+            Model::Unknown(_) => "Unknown",
+        }
+    }
+}
+
+impl FirmwareVersion {
+    /// Version 0.13, which changed the layout of the [`StatusFlags`].
+    pub const V0_13: Self = Self::new(0, 13);
+
+    /// Create a firmware version from its major and minor versions.
+    pub const fn new(major: u8, minor: u8) -> Self {
+        Self { major, minor }
+    }
+}
+
 impl LaserInfoHeader {
     /// The size of the header encoded as bytes.
     pub const SIZE: usize = 38;
+    /// The `temperature` reported when the device has no temperature sensor.
+    pub const TEMPERATURE_SENSOR_ABSENT: u8 = 255;
 
     /// Get whether interlock is enabled.
     pub fn interlock_enabled(&self) -> bool {
-        self.status.interlock_enabled(self.fw_major, self.fw_minor)
+        self.status.interlock_enabled_for(self.firmware())
     }
 
     /// Get whether a temperature warning is active.
     pub fn temperature_warning(&self) -> bool {
-        self.status
-            .temperature_warning(self.fw_major, self.fw_minor)
+        self.status.temperature_warning_for(self.firmware())
     }
 
     /// Get whether an over-temperature condition is active.
     pub fn over_temperature(&self) -> bool {
-        self.status.over_temperature(self.fw_major, self.fw_minor)
+        self.status.over_temperature_for(self.firmware())
+    }
+
+    /// Whether the fields are mutually consistent, as a sanity check against
+    /// corrupt packets.
+    ///
+    /// Checks that the battery percentage is at most 100, the DAC rate doesn't
+    /// exceed the maximum DAC rate, and the free buffer space doesn't exceed
+    /// the buffer size. An unknown connection type is accepted, as it may be
+    /// reported by legitimate devices.
+    pub fn looks_valid(&self) -> bool {
+        self.battery_percent <= 100
+            && self.dac_rate <= self.max_dac_rate
+            && self.rx_buffer_free <= self.rx_buffer_size
+    }
+
+    /// The battery charge as a fraction in the range `[0.0, 1.0]`.
+    pub fn battery_fraction(&self) -> f32 {
+        (self.battery_percent as f32 / 100.0).clamp(0.0, 1.0)
+    }
+
+    /// The device temperature in whole degrees Celsius.
+    ///
+    /// Returns `None` if the device reports `255`, indicating the temperature
+    /// sensor is absent.
+    pub fn temperature_celsius(&self) -> Option<i16> {
+        match self.temperature {
+            Self::TEMPERATURE_SENSOR_ABSENT => None,
+            t => Some(t as i16),
+        }
+    }
+
+    /// The firmware version, from `fw_major` and `fw_minor`.
+    pub fn firmware(&self) -> FirmwareVersion {
+        FirmwareVersion::new(self.fw_major, self.fw_minor)
+    }
+
+    /// The device model, derived from `model_number`.
+    pub fn model(&self) -> Model {
+        Model::from(self.model_number)
+    }
+
+    /// Get the safety-relevant status flags, resolved for the firmware layout.
+    pub fn safety_state(&self) -> SafetyState {
+        SafetyState {
+            output_enabled: self.status.output_enabled(),
+            interlock_enabled: self.interlock_enabled(),
+            temperature_warning: self.temperature_warning(),
+            over_temperature: self.over_temperature(),
+        }
+    }
+
+    /// Encode the header as bytes.
+    ///
+    /// This is the exact inverse of the `From<[u8; 38]>` implementation, with
+    /// the command echo byte set to `GetFullInfo` and all padding zeroed.
+    #[rustfmt::skip]
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let [dr0, dr1, dr2, dr3] = self.dac_rate.to_le_bytes();
+        let [mdr0, mdr1, mdr2, mdr3] = self.max_dac_rate.to_le_bytes();
+        let [rxbf0, rxbf1] = self.rx_buffer_free.to_le_bytes();
+        let [rxbs0, rxbs1] = self.rx_buffer_size.to_le_bytes();
+        let [sn0, sn1, sn2, sn3, sn4, sn5] = self.serial_number;
+        let [ip0, ip1, ip2, ip3] = self.ip_addr.octets();
+        [
+            CommandType::GetFullInfo as u8, // 0: Command echo
+            0,                              // 1: Padding
+            0,                              // 2: Padding
+            self.fw_major,                  // 3
+            self.fw_minor,                  // 4
+            self.status.bits(),             // 5
+            0, 0, 0, 0,                     // 6-9: 4 bytes padding
+            dr0, dr1, dr2, dr3,             // 10-13: DAC rate
+            mdr0, mdr1, mdr2, mdr3,         // 14-17: Max DAC rate
+            0,                              // 18: Padding
+            rxbf0, rxbf1,                   // 19-20: RX buffer free
+            rxbs0, rxbs1,                   // 21-22: RX buffer size
+            self.battery_percent,           // 23
+            self.temperature,               // 24
+            self.conn_type.to_u8(),         // 25
+            sn0, sn1, sn2, sn3, sn4, sn5,   // 26-31: Serial number
+            ip0, ip1, ip2, ip3,             // 32-35: IP address
+            0,                              // 36: Padding
+            self.model_number,              // 37
+        ]
+    }
+}
+
+impl Default for LaserInfoHeader {
+    /// A blank device with firmware 0.0, no status flags, default-sized
+    /// buffers, an unknown connection, a zeroed serial and an unspecified IP.
+    fn default() -> Self {
+        Self {
+            fw_major: 0,
+            fw_minor: 0,
+            status: StatusFlags::empty(),
+            dac_rate: 0,
+            max_dac_rate: 0,
+            rx_buffer_free: buffer::DEFAULT_SIZE,
+            rx_buffer_size: buffer::DEFAULT_SIZE,
+            battery_percent: 0,
+            temperature: 0,
+            model_number: 0,
+            conn_type: ConnectionType::Unknown,
+            serial_number: [0; 6],
+            ip_addr: Ipv4Addr::UNSPECIFIED,
+        }
     }
 }
 
+#[cfg(feature = "alloc")]
 impl LaserInfo {
     /// The minimum size of the `LaserInfo` in bytes.
     pub const MIN_SIZE: usize = LaserInfoHeader::SIZE;
@@ -124,9 +415,28 @@ impl LaserInfo {
     /// The maximum size of the `LaserInfo`'s model name field in bytes.
     pub const MAX_MODEL_NAME_SIZE: usize = Self::MAX_SIZE - Self::MIN_SIZE;
 
+    /// Whether `self` and `other` describe the same device with the same
+    /// capabilities, ignoring telemetry and state that change during use.
+    ///
+    /// Compares the serial number, IP address, connection type, firmware
+    /// version, model, max DAC rate and buffer size. Ignores the status flags,
+    /// current DAC rate, free buffer space, battery and temperature.
+    pub fn identity_eq(&self, other: &Self) -> bool {
+        let (a, b) = (&self.header, &other.header);
+        a.serial_number == b.serial_number
+            && a.ip_addr == b.ip_addr
+            && a.conn_type == b.conn_type
+            && a.fw_major == b.fw_major
+            && a.fw_minor == b.fw_minor
+            && a.model_number == b.model_number
+            && a.max_dac_rate == b.max_dac_rate
+            && a.rx_buffer_size == b.rx_buffer_size
+            && self.model_name == other.model_name
+    }
+
     /// Get the firmware version as a string (e.g., "1.2")
     pub fn firmware_version(&self) -> String {
-        format!("{}.{}", self.header.fw_major, self.header.fw_minor)
+        format!("{}", self.header.firmware())
     }
 
     /// Get the serial number as a formatted string (XX:XX:XX:XX:XX:XX)
@@ -136,11 +446,103 @@ impl LaserInfo {
             if i > 0 {
                 result.push(':');
             }
-            use std::fmt::Write;
+            use core::fmt::Write;
             write!(result, "{:02x}", byte).unwrap();
         }
         result
     }
+
+    /// The model name as it will be encoded by [`LaserInfo::write_bytes`].
+    ///
+    /// Names that would not fit within `MAX_MODEL_NAME_SIZE` alongside the
+    /// null terminator are truncated at the last whole character that fits.
+    pub fn encoded_model_name(&self) -> &str {
+        let max_len = Self::MAX_MODEL_NAME_SIZE - 1;
+        let name = &self.model_name;
+        if name.len() <= max_len {
+            return name;
+        }
+        let end = (0..=max_len)
+            .rev()
+            .find(|&i| name.is_char_boundary(i))
+            .unwrap_or(0);
+        &name[..end]
+    }
+
+    /// The size of the `LaserInfo` in bytes when encoded.
+    pub fn size(&self) -> usize {
+        LaserInfoHeader::SIZE + self.encoded_model_name().len() + 1
+    }
+
+    /// Write this `LaserInfo` into the provided byte buffer.
+    ///
+    /// Appends the header, the model name and a null terminator. The model
+    /// name is truncated if necessary (see [`LaserInfo::encoded_model_name`])
+    /// so that the result never exceeds `MAX_SIZE`.
+    ///
+    /// Returns the number of bytes written.
+    pub fn write_bytes(&self, buffer: &mut Vec<u8>) -> usize {
+        let start_len = buffer.len();
+        buffer.extend_from_slice(&self.header.to_bytes());
+        buffer.extend_from_slice(self.encoded_model_name().as_bytes());
+        buffer.push(0);
+        buffer.len() - start_len
+    }
+
+    /// Convenience method to get the encoded bytes as a new `Vec<u8>`
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buffer = Vec::with_capacity(self.size());
+        self.write_bytes(&mut buffer);
+        buffer
+    }
+}
+
+/// Orders by serial number, then IP address, then the remaining fields.
+impl Ord for LaserInfoHeader {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        let rest = |h: &Self| {
+            (
+                h.fw_major,
+                h.fw_minor,
+                h.status.bits(),
+                h.dac_rate,
+                h.max_dac_rate,
+                h.rx_buffer_free,
+                h.rx_buffer_size,
+                h.battery_percent,
+                h.temperature,
+                h.model_number,
+                h.conn_type.to_u8(),
+            )
+        };
+        self.serial_number
+            .cmp(&other.serial_number)
+            .then_with(|| self.ip_addr.cmp(&other.ip_addr))
+            .then_with(|| rest(self).cmp(&rest(other)))
+    }
+}
+
+impl PartialOrd for LaserInfoHeader {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Orders by header (serial number, then IP address first), then model name.
+#[cfg(feature = "alloc")]
+impl Ord for LaserInfo {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.header
+            .cmp(&other.header)
+            .then_with(|| self.model_name.cmp(&other.model_name))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PartialOrd for LaserInfo {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl From<u8> for ConnectionType {
@@ -154,6 +556,37 @@ impl From<u8> for ConnectionType {
     }
 }
 
+impl core::fmt::Display for ConnectionType {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<u8> for Model {
+    fn from(value: u8) -> Self {
+        match value {
+            1 => Model::LaserCube,
+            2 => Model::LaserCubePro,
+            n => Model::Unknown(n),
+        }
+    }
+}
+
+impl core::fmt::Display for Model {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            Model::Unknown(n) => write!(f, "Unknown ({n})"),
+            model => f.write_str(model.as_str()),
+        }
+    }
+}
+
+impl core::fmt::Display for FirmwareVersion {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
 impl From<[u8; 38]> for LaserInfoHeader {
     fn from(bytes: [u8; 38]) -> Self {
         #[rustfmt::skip]
@@ -197,6 +630,7 @@ impl From<[u8; 38]> for LaserInfoHeader {
     }
 }
 
+#[cfg(feature = "alloc")]
 impl TryFrom<&[u8]> for LaserInfo {
     type Error = LaserInfoParseError;
 
@@ -205,21 +639,51 @@ impl TryFrom<&[u8]> for LaserInfo {
         let header_bytes: &[u8; LaserInfoHeader::SIZE] = bytes
             .get(0..LaserInfoHeader::SIZE)
             .and_then(|slice| slice.try_into().ok())
-            .ok_or_else(|| LaserInfoParseError::ResponseTooShort {
+            .ok_or(LaserInfoParseError::ResponseTooShort {
                 expected: LaserInfoHeader::SIZE,
                 actual: bytes.len(),
             })?;
         // Parse the fixed header portion
         let header = LaserInfoHeader::from(*header_bytes);
         // Model name is a null-terminated string starting after the fixed region.
-        let model_name_start = LaserInfoHeader::SIZE;
-        let model_name_cstr = CStr::from_bytes_until_nul(&bytes[model_name_start..])?;
-        let model_name = String::from_utf8_lossy(model_name_cstr.to_bytes()).to_string();
+        // Some firmware fills the entire field without a terminator, in which
+        // case the remaining bytes (up to the max field size) are used.
+        let model_name_bytes = &bytes[LaserInfoHeader::SIZE..];
+        let max_len = model_name_bytes.len().min(Self::MAX_MODEL_NAME_SIZE);
+        let model_name_bytes = &model_name_bytes[..max_len];
+        let len = model_name_bytes
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(max_len);
+        let model_name = String::from_utf8_lossy(&model_name_bytes[..len]).into_owned();
         Ok(LaserInfo { header, model_name })
     }
 }
 
-#[cfg(test)]
+/// The maximum number of bytes shown by [`hex_preview`].
+pub const HEX_PREVIEW_LEN: usize = 64;
+
+/// Format the start of a packet as space separated hex bytes for logging.
+///
+/// At most [`HEX_PREVIEW_LEN`] bytes are shown, followed by the total length
+/// if the packet was truncated.
+#[cfg(feature = "alloc")]
+pub fn hex_preview(bytes: &[u8]) -> String {
+    use core::fmt::Write;
+    let mut preview = String::with_capacity(HEX_PREVIEW_LEN * 3 + 16);
+    for (i, byte) in bytes.iter().take(HEX_PREVIEW_LEN).enumerate() {
+        if i > 0 {
+            preview.push(' ');
+        }
+        let _ = write!(preview, "{byte:02x}");
+    }
+    if bytes.len() > HEX_PREVIEW_LEN {
+        let _ = write!(preview, " ... ({} bytes)", bytes.len());
+    }
+    preview
+}
+
+#[cfg(all(test, feature = "alloc"))]
 mod tests {
     use super::*;
 
@@ -280,7 +744,6 @@ mod tests {
         assert_eq!(info_header.fw_major, 1);
         assert_eq!(info_header.fw_minor, 2);
         assert!(info_header.status.output_enabled());
-        assert_eq!(info_header.status.output_enabled(), true);
         assert_eq!(info_header.dac_rate, 6000);
         assert_eq!(info_header.max_dac_rate, 6000);
         assert_eq!(info_header.rx_buffer_free, 5000);
@@ -340,6 +803,86 @@ mod tests {
         assert!(info_header.over_temperature());
     }
 
+    #[test]
+    fn test_default_round_trip() {
+        let info = LaserInfo::default();
+        assert_eq!(info.header.rx_buffer_size, buffer::DEFAULT_SIZE);
+        assert_eq!(info.header.conn_type, ConnectionType::Unknown);
+        let bytes = info.to_bytes();
+        assert_eq!(LaserInfo::try_from(&bytes[..]).unwrap(), info);
+        let header = LaserInfoHeader::default();
+        assert_eq!(LaserInfoHeader::from(header.to_bytes()), header);
+    }
+
+    #[test]
+    fn test_identity_eq() {
+        let info = test_laser_info("LaserCube");
+        let mut other = info.clone();
+        other.header.status = StatusFlags::empty();
+        other.header.dac_rate = 20_000;
+        other.header.rx_buffer_free = 10;
+        other.header.battery_percent = 12;
+        other.header.temperature = 55;
+        assert!(info.identity_eq(&other));
+        assert_ne!(info, other);
+
+        other.header.ip_addr = Ipv4Addr::new(10, 0, 0, 43);
+        assert!(!info.identity_eq(&other));
+        let mut other = info.clone();
+        other.header.fw_minor += 1;
+        assert!(!info.identity_eq(&other));
+        let mut other = info.clone();
+        other.model_name.push('2');
+        assert!(!info.identity_eq(&other));
+    }
+
+    #[test]
+    fn test_battery_fraction() {
+        let mut header = test_laser_info("").header;
+        for (percent, fraction) in [(0, 0.0), (50, 0.5), (87, 0.87), (100, 1.0), (200, 1.0)] {
+            header.battery_percent = percent;
+            assert_eq!(header.battery_fraction(), fraction);
+        }
+    }
+
+    #[test]
+    fn test_temperature_celsius() {
+        let mut header = test_laser_info("").header;
+        for (raw, celsius) in [(0, Some(0)), (42, Some(42)), (254, Some(254)), (255, None)] {
+            header.temperature = raw;
+            assert_eq!(header.temperature_celsius(), celsius);
+        }
+    }
+
+    #[test]
+    fn test_safety_state() {
+        let mut header = test_laser_info("").header;
+
+        header.fw_minor = 12;
+        header.status = StatusFlags::encode(0, 12, true, true, false, true, 0);
+        assert_eq!(
+            header.safety_state(),
+            SafetyState {
+                output_enabled: true,
+                interlock_enabled: true,
+                temperature_warning: false,
+                over_temperature: true,
+            }
+        );
+
+        header.fw_minor = 13;
+        header.status = StatusFlags::encode(0, 13, false, false, true, true, 1);
+        assert_eq!(
+            header.safety_state(),
+            SafetyState {
+                output_enabled: false,
+                interlock_enabled: false,
+                temperature_warning: true,
+                over_temperature: true,
+            }
+        );
+    }
+
     #[test]
     fn test_parse_laser_info_with_header() {
         // Create a test header array
@@ -405,7 +948,7 @@ mod tests {
 
         assert_eq!(laser_info.header.fw_major, 1);
         assert_eq!(laser_info.header.fw_minor, 2);
-        assert_eq!(laser_info.header.status.output_enabled(), true);
+        assert!(laser_info.header.status.output_enabled());
         assert_eq!(laser_info.header.dac_rate, 6000);
         assert_eq!(laser_info.header.max_dac_rate, 6000);
         assert_eq!(laser_info.header.rx_buffer_free, 5000);
@@ -421,4 +964,221 @@ mod tests {
         );
         assert_eq!(laser_info.model_name, "LaserCube Pro");
     }
+
+    #[test]
+    fn test_parse_model_name_without_null_terminator() {
+        let mut message = [0u8; LaserInfo::MAX_SIZE];
+        message[0] = CommandType::GetFullInfo as u8;
+        let model_name = b"LaserCube Ultra 2W Edition";
+        assert_eq!(model_name.len(), LaserInfo::MAX_MODEL_NAME_SIZE);
+        message[LaserInfoHeader::SIZE..].copy_from_slice(model_name);
+        let laser_info = LaserInfo::try_from(&message[..]).unwrap();
+        assert_eq!(laser_info.model_name, "LaserCube Ultra 2W Edition");
+
+        // Trailing bytes beyond the max size are ignored.
+        let mut long_message = message.to_vec();
+        long_message.extend_from_slice(b"garbage");
+        let laser_info = LaserInfo::try_from(&long_message[..]).unwrap();
+        assert_eq!(laser_info.model_name, "LaserCube Ultra 2W Edition");
+
+        // A header with no model name bytes at all.
+        let laser_info = LaserInfo::try_from(&message[..LaserInfoHeader::SIZE]).unwrap();
+        assert_eq!(laser_info.model_name, "");
+    }
+
+    #[test]
+    fn test_connection_type_display() {
+        assert_eq!(ConnectionType::Unknown.as_str(), "unknown");
+        assert_eq!(ConnectionType::Usb.as_str(), "usb");
+        assert_eq!(ConnectionType::Ethernet.as_str(), "ethernet");
+        assert_eq!(ConnectionType::Wifi.as_str(), "wifi");
+        assert_eq!(ConnectionType::Unknown.to_string(), "unknown");
+        assert_eq!(ConnectionType::Usb.to_string(), "usb");
+        assert_eq!(ConnectionType::Ethernet.to_string(), "ethernet");
+        assert_eq!(ConnectionType::Wifi.to_string(), "wifi");
+    }
+
+    #[test]
+    fn test_connection_type_round_trip() {
+        for (i, ct) in ConnectionType::all().into_iter().enumerate() {
+            assert_eq!(ct.to_u8(), i as u8);
+            assert_eq!(ConnectionType::from(ct.to_u8()), ct);
+        }
+        assert_eq!(ConnectionType::from(4), ConnectionType::Unknown);
+    }
+
+    #[test]
+    fn test_hash_and_ord() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let a = test_laser_info("LaserCube");
+        let mut b = a.clone();
+        b.header.serial_number = [0, 0, 0, 0, 0, 1];
+        let mut c = a.clone();
+        c.header.ip_addr = Ipv4Addr::new(10, 0, 0, 1);
+
+        let set: HashSet<LaserInfo> = [a.clone(), b.clone(), a.clone()].into_iter().collect();
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(&a) && set.contains(&b));
+
+        // Ordered by serial number, then IP address.
+        let set: BTreeSet<LaserInfo> = [a.clone(), b.clone(), c.clone()].into_iter().collect();
+        let ordered: Vec<&LaserInfo> = set.iter().collect();
+        assert_eq!(ordered, [&b, &c, &a]);
+
+        // Other fields break ties consistently with `Eq`.
+        let mut d = a.clone();
+        d.header.temperature += 1;
+        assert_ne!(a.cmp(&d), core::cmp::Ordering::Equal);
+        assert_eq!(a.cmp(&a.clone()), core::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn test_model() {
+        let mut header = LaserInfoHeader {
+            model_number: 2,
+            ..Default::default()
+        };
+        assert_eq!(header.model(), Model::LaserCubePro);
+        assert_eq!(header.model().as_str(), "LaserCube Pro");
+        assert_eq!(header.model().to_u8(), 2);
+
+        header.model_number = 0x42;
+        assert_eq!(header.model(), Model::Unknown(0x42));
+        assert_eq!(header.model().as_str(), "Unknown");
+        assert_eq!(header.model().to_string(), "Unknown (66)");
+        assert_eq!(header.model().to_u8(), 0x42);
+    }
+
+    #[test]
+    fn test_looks_valid() {
+        let valid = test_laser_info("LaserCube").header;
+        assert!(valid.looks_valid());
+        assert!(LaserInfoHeader::default().looks_valid());
+
+        let corrupt = [
+            LaserInfoHeader {
+                battery_percent: 200,
+                ..valid
+            },
+            LaserInfoHeader {
+                dac_rate: 4_000_000_000,
+                ..valid
+            },
+            LaserInfoHeader {
+                rx_buffer_free: valid.rx_buffer_size + 1,
+                ..valid
+            },
+        ];
+        for header in corrupt {
+            assert!(!header.looks_valid(), "{header:?}");
+        }
+    }
+
+    #[test]
+    fn test_firmware_version() {
+        let v0_12 = FirmwareVersion::new(0, 12);
+        let v1_0 = FirmwareVersion::new(1, 0);
+        assert!(v0_12 < FirmwareVersion::V0_13);
+        assert!(FirmwareVersion::V0_13 < v1_0);
+        assert!(v0_12 < v1_0);
+        assert_eq!(FirmwareVersion::V0_13.to_string(), "0.13");
+
+        let header = LaserInfoHeader {
+            fw_major: 0,
+            fw_minor: 13,
+            ..Default::default()
+        };
+        assert!(
+            header.firmware()
+                >= FirmwareVersion {
+                    major: 0,
+                    minor: 13
+                }
+        );
+        assert_eq!(test_laser_info("LaserCube").firmware_version(), "0.13");
+    }
+
+    fn test_laser_info(model_name: &str) -> LaserInfo {
+        LaserInfo {
+            header: LaserInfoHeader {
+                fw_major: 0,
+                fw_minor: 13,
+                status: StatusFlags::from_bits_retain(0x2F),
+                dac_rate: 30_000,
+                max_dac_rate: 40_000,
+                rx_buffer_free: 5000,
+                rx_buffer_size: 6000,
+                battery_percent: 87,
+                temperature: 42,
+                model_number: 3,
+                conn_type: ConnectionType::Wifi,
+                serial_number: [0xde, 0xad, 0xbe, 0xef, 0x01, 0x02],
+                ip_addr: Ipv4Addr::new(10, 0, 0, 42),
+            },
+            model_name: model_name.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_laser_info_header_round_trip() {
+        let info = test_laser_info("");
+        let bytes = info.header.to_bytes();
+        assert_eq!(bytes[0], CommandType::GetFullInfo as u8);
+        assert_eq!(LaserInfoHeader::from(bytes), info.header);
+    }
+
+    #[test]
+    fn test_laser_info_round_trip() {
+        let max_name = "x".repeat(LaserInfo::MAX_MODEL_NAME_SIZE - 1);
+        for name in ["", "LaserCube", "LaserCube Pro", &max_name] {
+            let info = test_laser_info(name);
+            let bytes = info.to_bytes();
+            assert_eq!(bytes.len(), info.size());
+            assert!(bytes.len() <= LaserInfo::MAX_SIZE);
+            assert_eq!(LaserInfo::try_from(&bytes[..]).unwrap(), info);
+        }
+    }
+
+    #[test]
+    fn test_laser_info_long_model_name_truncated() {
+        // Multi-byte characters must not be split by truncation.
+        let name = "é".repeat(LaserInfo::MAX_MODEL_NAME_SIZE);
+        let info = test_laser_info(&name);
+        let bytes = info.to_bytes();
+        assert!(bytes.len() <= LaserInfo::MAX_SIZE);
+        let parsed = LaserInfo::try_from(&bytes[..]).unwrap();
+        assert_eq!(parsed.model_name, info.encoded_model_name());
+        assert!(name.starts_with(&parsed.model_name));
+        assert_eq!(parsed.model_name.chars().count(), 12);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_json_round_trip() {
+        let info = test_laser_info("LaserCube");
+        let json = serde_json::to_string(&info).unwrap();
+        assert_eq!(serde_json::from_str::<LaserInfo>(&json).unwrap(), info);
+
+        let point = Point::new([0x123, 0xFFF], [0, 0x800, 0xFFF]);
+        let json = serde_json::to_string(&point).unwrap();
+        assert_eq!(serde_json::from_str::<Point>(&json).unwrap(), point);
+
+        let buffer = BufferState::new();
+        let json = serde_json::to_string(&buffer).unwrap();
+        let restored: BufferState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.free_space, buffer.free_space);
+        assert_eq!(restored.threshold, buffer.threshold);
+    }
+
+    #[test]
+    fn test_hex_preview() {
+        assert_eq!(hex_preview(&[]), "");
+        assert_eq!(hex_preview(&[0x8a, 0x00, 0xe8, 0x03]), "8a 00 e8 03");
+
+        let long: Vec<u8> = (0..100).collect();
+        let preview = hex_preview(&long);
+        assert!(preview.starts_with("00 01 02 "), "{preview}");
+        assert!(preview.ends_with(" 3e 3f ... (100 bytes)"), "{preview}");
+    }
 }
@@ -1,10 +1,15 @@
 //! Point data representation for laser rendering.
 
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use thiserror::Error;
+
 /// A single point to be rendered by the laser.
 ///
 /// Coordinates are in the range 0-0xFFF, with 0x800 being the center.
 /// Color values are in the range 0-0xFFF.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point {
     /// Each coordinate (0x000-0xFFF, 0x800 is center)
     pub pos: Position,
@@ -12,6 +17,65 @@ pub struct Point {
     pub rgb: Rgb,
 }
 
+/// Error returned when a point field exceeds its valid 12-bit range.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum PointError {
+    #[error("X coordinate {0:#x} exceeds maximum {max:#x}", max = Point::MAX_COORD)]
+    X(u16),
+    #[error("Y coordinate {0:#x} exceeds maximum {max:#x}", max = Point::MAX_COORD)]
+    Y(u16),
+    #[error("Red channel {0:#x} exceeds maximum {max:#x}", max = Point::MAX_COLOR)]
+    Red(u16),
+    #[error("Green channel {0:#x} exceeds maximum {max:#x}", max = Point::MAX_COLOR)]
+    Green(u16),
+    #[error("Blue channel {0:#x} exceeds maximum {max:#x}", max = Point::MAX_COLOR)]
+    Blue(u16),
+}
+
+/// Error returned when a normalized value lies outside of its valid range.
+///
+/// Carries the offending value.
+#[derive(Debug, Error, Clone, Copy, PartialEq)]
+#[error("Normalized value {0} is out of range")]
+pub struct OutOfRange(pub f32);
+
+/// Error returned when decoding a byte buffer into points.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    #[error("{remainder} trailing bytes do not form a whole point")]
+    TrailingBytes { remainder: usize },
+}
+
+/// Per-channel color correction, applied to a point's `rgb` before sending.
+///
+/// Requires the `std` feature.
+///
+/// Each channel is remapped as `gain * value.powf(gamma)` in normalized color
+/// space, with the result clamped to `MAX_COLOR`. The default profile is the
+/// identity and leaves points untouched.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorProfile {
+    /// Gamma exponent for the red, green and blue channels.
+    pub gamma: [f32; 3],
+    /// Gain multiplier for the red, green and blue channels.
+    pub gain: [f32; 3],
+}
+
+/// Per-channel mask for devices that can only show some colors, e.g. single
+/// color lasers or those with a failed diode.
+///
+/// Disabled channels are zeroed. Apply after any [`ColorProfile`] correction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ColorMask {
+    /// Whether the red channel is enabled.
+    pub r: bool,
+    /// Whether the green channel is enabled.
+    pub g: bool,
+    /// Whether the blue channel is enabled.
+    pub b: bool,
+}
+
 /// Each coordinate (0x000-0xFFF, 0x800 is center)
 pub type Position = [u16; 2];
 
@@ -39,6 +103,47 @@ impl Point {
         Self { pos, rgb }
     }
 
+    /// Create a new point, checking that all fields are within range.
+    ///
+    /// Returns an error naming the first coordinate exceeding `MAX_COORD` or
+    /// color channel exceeding `MAX_COLOR`.
+    pub fn try_new(pos: Position, rgb: Rgb) -> Result<Self, PointError> {
+        let ([x, y], [r, g, b]) = (pos, rgb);
+        if x > Self::MAX_COORD {
+            return Err(PointError::X(x));
+        }
+        if y > Self::MAX_COORD {
+            return Err(PointError::Y(y));
+        }
+        if r > Self::MAX_COLOR {
+            return Err(PointError::Red(r));
+        }
+        if g > Self::MAX_COLOR {
+            return Err(PointError::Green(g));
+        }
+        if b > Self::MAX_COLOR {
+            return Err(PointError::Blue(b));
+        }
+        Ok(Self::new(pos, rgb))
+    }
+
+    /// Whether all coordinates and color channels are within their valid range.
+    pub fn is_valid(&self) -> bool {
+        Self::try_new(self.pos, self.rgb).is_ok()
+    }
+
+    /// A copy of this point with each coordinate clamped to `MAX_COORD` and
+    /// each color channel clamped to `MAX_COLOR`.
+    ///
+    /// The integer-space complement of the clamping performed when converting
+    /// from normalized values.
+    pub fn clamp(&self) -> Point {
+        Point {
+            pos: self.pos.map(|c| c.min(Self::MAX_COORD)),
+            rgb: self.rgb.map(|c| c.min(Self::MAX_COLOR)),
+        }
+    }
+
     /// Create a point from normalized coordinates and colors.
     ///
     /// Coordinates should be in the range [-1.0, 1.0], with (0.0, 0.0) being the center.
@@ -52,6 +157,52 @@ impl Point {
         Self::new([x, y], [r, g, b])
     }
 
+    /// Create a point from normalized coordinates and 8-bit colors, e.g. from
+    /// standard 8-bit sRGB content.
+    ///
+    /// Coordinates should be in the range [-1.0, 1.0], with (0.0, 0.0) being the center.
+    /// Colors are scaled to 12 bits with [`color_from_rgb8`].
+    pub fn from_normalized_rgb8([x, y]: [f32; 2], [r, g, b]: [u8; 3]) -> Self {
+        let x = coord_from_normalized(x);
+        let y = coord_from_normalized(y);
+        let rgb = [r, g, b].map(color_from_rgb8);
+        Self::new([x, y], rgb)
+    }
+
+    /// Create a point from normalized coordinates with a single intensity
+    /// applied to a base color, e.g. for monochrome text or vector content.
+    ///
+    /// Each channel of `color` is multiplied by `intensity` before converting,
+    /// with all values in the range [0.0, 1.0]. Out of range values are
+    /// clamped as in [`Point::from_normalized`].
+    pub fn mono(pos: [f32; 2], intensity: f32, color: [f32; 3]) -> Self {
+        Self::from_normalized(pos, color.map(|c| c * intensity))
+    }
+
+    /// Create a point from normalized coordinates and an HSV color.
+    ///
+    /// See [`rgb_from_hsv`] for the ranges of `h`, `s` and `v`.
+    pub fn from_hsv(pos: [f32; 2], h: f32, s: f32, v: f32) -> Self {
+        Self::from_normalized(pos, rgb_from_hsv(h, s, v))
+    }
+
+    /// Create a point from normalized coordinates and colors, returning an
+    /// error rather than clamping if any value is out of range.
+    ///
+    /// Coordinates must be in the range [-1.0, 1.0] and colors in the range
+    /// [0.0, 1.0]. See [`Point::from_normalized`] for the clamping equivalent.
+    pub fn from_normalized_checked(
+        [x, y]: [f32; 2],
+        [r, g, b]: [f32; 3],
+    ) -> Result<Self, OutOfRange> {
+        let x = coord_from_normalized_checked(x)?;
+        let y = coord_from_normalized_checked(y)?;
+        let r = color_from_normalized_checked(r)?;
+        let g = color_from_normalized_checked(g)?;
+        let b = color_from_normalized_checked(b)?;
+        Ok(Self::new([x, y], [r, g, b]))
+    }
+
     /// Convert to normalized coordinates and colors.
     ///
     /// Returns coordinates in the range [-1.0, 1.0], with (0.0, 0.0) being the center.
@@ -64,6 +215,143 @@ impl Point {
         let b_norm = normalized_from_color(self.rgb[2]);
         ([x_norm, y_norm], [r_norm, g_norm, b_norm])
     }
+
+    /// Linearly interpolate both position and color between `self` and `other`.
+    ///
+    /// `t` is clamped to the range [0.0, 1.0], where `0.0` produces `self` and
+    /// `1.0` produces `other`. Each channel is rounded to the nearest value.
+    pub fn lerp(&self, other: &Point, t: f32) -> Point {
+        let t = t.clamp(0.0, 1.0);
+        let [x, y] = [0, 1].map(|i| lerp_u16(self.pos[i], other.pos[i], t));
+        let [r, g, b] = [0, 1, 2].map(|i| lerp_u16(self.rgb[i], other.rgb[i], t));
+        Point::new([x, y], [r, g, b])
+    }
+
+    /// A copy of this point at the same position with the laser off.
+    pub const fn blanked(&self) -> Point {
+        self.with_color(Self::BLANK)
+    }
+
+    /// Whether the laser is off for this point, i.e. all color channels are zero.
+    pub fn is_blank(&self) -> bool {
+        self.rgb == Self::BLANK
+    }
+
+    /// The perceived brightness of the point's color in the range [0.0, 1.0].
+    ///
+    /// Computed from the normalized channels using the Rec. 709 luma weights
+    /// `0.2126 R + 0.7152 G + 0.0722 B`.
+    pub fn luminance(&self) -> f32 {
+        let (_, [r, g, b]) = self.to_normalized();
+        (0.2126 * r + 0.7152 * g + 0.0722 * b).clamp(0.0, 1.0)
+    }
+
+    /// A copy of this point with the given color.
+    pub const fn with_color(self, rgb: Rgb) -> Point {
+        Point::new(self.pos, rgb)
+    }
+
+    /// A copy of this point at the given position.
+    pub const fn with_pos(self, pos: Position) -> Point {
+        Point::new(pos, self.rgb)
+    }
+
+    /// The Euclidean distance between the positions of `self` and `other` in
+    /// normalized coordinate space.
+    ///
+    /// The distance between opposite corners is `2.0 * SQRT_2`.
+    ///
+    /// Requires the `std` feature.
+    #[cfg(feature = "std")]
+    pub fn distance(&self, other: &Point) -> f32 {
+        let ([ax, ay], _) = self.to_normalized();
+        let ([bx, by], _) = other.to_normalized();
+        (bx - ax).hypot(by - ay)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ColorProfile {
+    /// The identity profile.
+    pub const IDENTITY: Self = Self {
+        gamma: [1.0; 3],
+        gain: [1.0; 3],
+    };
+
+    /// Set the per-channel gamma exponents.
+    pub fn with_gamma(mut self, gamma: [f32; 3]) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    /// Set the per-channel gain multipliers.
+    pub fn with_gain(mut self, gain: [f32; 3]) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Apply the color correction to the given point.
+    pub fn apply(&self, point: Point) -> Point {
+        let rgb = [0, 1, 2].map(|i| {
+            let color = normalized_from_color(point.rgb[i]);
+            let corrected = self.gain[i] * color.powf(self.gamma[i]);
+            let scaled = corrected.clamp(0.0, 1.0) * Point::MAX_COLOR as f32;
+            scaled.round() as u16
+        });
+        Point::new(point.pos, rgb)
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for ColorProfile {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl ColorMask {
+    /// All channels enabled, leaving points untouched.
+    pub const ALL: Self = Self::new(true, true, true);
+    /// Only the red channel enabled.
+    pub const RED_ONLY: Self = Self::new(true, false, false);
+    /// Only the green channel enabled.
+    pub const GREEN_ONLY: Self = Self::new(false, true, false);
+    /// Only the blue channel enabled.
+    pub const BLUE_ONLY: Self = Self::new(false, false, true);
+
+    /// Create a mask enabling the given channels.
+    pub const fn new(r: bool, g: bool, b: bool) -> Self {
+        Self { r, g, b }
+    }
+
+    /// Zero the disabled channels of the given point.
+    pub const fn apply(&self, point: Point) -> Point {
+        let [r, g, b] = point.rgb;
+        let rgb = [
+            if self.r { r } else { 0 },
+            if self.g { g } else { 0 },
+            if self.b { b } else { 0 },
+        ];
+        point.with_color(rgb)
+    }
+}
+
+impl Default for ColorMask {
+    fn default() -> Self {
+        Self::ALL
+    }
+}
+
+impl From<(Position, Rgb)> for Point {
+    fn from((pos, rgb): (Position, Rgb)) -> Self {
+        Point::new(pos, rgb)
+    }
+}
+
+impl From<Point> for (Position, Rgb) {
+    fn from(p: Point) -> Self {
+        (p.pos, p.rgb)
+    }
 }
 
 impl From<Point> for [u8; Point::SIZE] {
@@ -89,20 +377,322 @@ impl From<[u8; Point::SIZE]> for Point {
     }
 }
 
+/// Decode a buffer of consecutive 10-byte points.
+///
+/// Returns a `TrailingBytes` error if the length of `bytes` is not a multiple
+/// of [`Point::SIZE`].
+#[cfg(feature = "alloc")]
+pub fn decode_all(bytes: &[u8]) -> Result<Vec<Point>, DecodeError> {
+    let remainder = bytes.len() % Point::SIZE;
+    if remainder != 0 {
+        return Err(DecodeError::TrailingBytes { remainder });
+    }
+    Ok(iter_points(bytes).collect())
+}
+
+/// Lazily decode a buffer of consecutive 10-byte points without allocating.
+///
+/// Any trailing bytes that don't form a whole point are skipped. See
+/// [`decode_all`] to treat them as an error instead.
+pub fn iter_points(bytes: &[u8]) -> impl ExactSizeIterator<Item = Point> + '_ {
+    bytes.chunks_exact(Point::SIZE).map(|chunk| {
+        let bytes: [u8; Point::SIZE] = chunk.try_into().expect("chunk is Point::SIZE bytes");
+        Point::from(bytes)
+    })
+}
+
+/// Encode points as consecutive 10-byte little-endian records.
+///
+/// This is the inverse of [`decode_all`].
+#[cfg(feature = "alloc")]
+pub fn encode_all(points: &[Point]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(points.len() * Point::SIZE);
+    for &point in points {
+        bytes.extend_from_slice(&<[u8; Point::SIZE]>::from(point));
+    }
+    bytes
+}
+
+/// Generate a line segment as a run of points with blanked endpoints.
+///
+/// Produces a blank point at `start`, followed by `steps` evenly spaced lit
+/// points from `start` to `end` (inclusive), followed by a blank point at
+/// `end`. The blank points allow the galvos to settle before and after the
+/// segment is drawn without leaving stray beams.
+///
+/// Positions and colors are normalized as in [`Point::from_normalized`].
+///
+/// A `steps` of `0` produces only the two blank points, while a `steps` of `1`
+/// produces a single lit point at `start` between them.
+#[cfg(feature = "alloc")]
+pub fn line(start: [f32; 2], end: [f32; 2], color: [f32; 3], steps: usize) -> Vec<Point> {
+    let blank = [0.0; 3];
+    let mut points = Vec::with_capacity(steps + 2);
+    points.push(Point::from_normalized(start, blank));
+    let last = steps.saturating_sub(1).max(1) as f32;
+    for i in 0..steps {
+        let t = i as f32 / last;
+        let x = start[0] + (end[0] - start[0]) * t;
+        let y = start[1] + (end[1] - start[1]) * t;
+        points.push(Point::from_normalized([x, y], color));
+    }
+    points.push(Point::from_normalized(end, blank));
+    points
+}
+
+/// Concatenate path segments, hiding the jumps between them with blank points.
+///
+/// Between each pair of consecutive segments, `blank_points` blanked points
+/// are inserted, evenly spaced along the jump from the end of one segment to
+/// the start of the next, with the last landing on the start of the next.
+/// This gives the galvos time to move and settle with the laser off rather
+/// than drawing a bright line between the segments.
+///
+/// If `blank_ends` is `true`, `blank_points` blanked points are also inserted
+/// at the start of the first segment and at the end of the last segment,
+/// e.g. to hide the jump to and from surrounding content.
+///
+/// Empty segments are skipped.
+#[cfg(feature = "alloc")]
+pub fn join_segments(segments: &[Vec<Point>], blank_points: usize, blank_ends: bool) -> Vec<Point> {
+    let mut segments = segments.iter().filter(|s| !s.is_empty());
+    let Some(first) = segments.next() else {
+        return Vec::new();
+    };
+    let mut points = Vec::new();
+    if blank_ends {
+        points.extend(core::iter::repeat_n(first[0].blanked(), blank_points));
+    }
+    points.extend_from_slice(first);
+    for segment in segments {
+        let (end, start) = (points[points.len() - 1], segment[0]);
+        for i in 1..=blank_points {
+            let t = i as f32 / blank_points as f32;
+            points.push(end.lerp(&start, t).blanked());
+        }
+        points.extend_from_slice(segment);
+    }
+    if blank_ends {
+        let last = points[points.len() - 1].blanked();
+        points.extend(core::iter::repeat_n(last, blank_points));
+    }
+    points
+}
+
+/// Generate a regular polygon centered at the origin as a closed loop.
+///
+/// Vertices lie on a circle of the given normalized `radius`, with the first
+/// vertex at angle `rotation` (in radians, counter-clockwise from the positive
+/// x axis). Each edge contributes `points_per_edge` evenly spaced lit points
+/// beginning at its first vertex, and a final point at the first vertex closes
+/// the loop, producing `sides * points_per_edge + 1` points in total.
+///
+/// Returns an empty `Vec` if `sides` is less than `3`. A `points_per_edge` of
+/// `0` is treated as `1`.
+///
+/// Requires the `std` feature.
+#[cfg(feature = "std")]
+pub fn polygon(
+    sides: usize,
+    radius: f32,
+    rotation: f32,
+    color: [f32; 3],
+    points_per_edge: usize,
+) -> Vec<Point> {
+    if sides < 3 {
+        return Vec::new();
+    }
+    let points_per_edge = points_per_edge.max(1);
+    let vertex = |i: usize| {
+        let angle = rotation + core::f32::consts::TAU * i as f32 / sides as f32;
+        [radius * angle.cos(), radius * angle.sin()]
+    };
+    let mut points = Vec::with_capacity(sides * points_per_edge + 1);
+    for i in 0..sides {
+        let (start, end) = (vertex(i), vertex(i + 1));
+        for j in 0..points_per_edge {
+            let t = j as f32 / points_per_edge as f32;
+            let x = start[0] + (end[0] - start[0]) * t;
+            let y = start[1] + (end[1] - start[1]) * t;
+            points.push(Point::from_normalized([x, y], color));
+        }
+    }
+    points.push(Point::from_normalized(vertex(0), color));
+    points
+}
+
+/// Scale and translate the positions of `points` in normalized space.
+///
+/// Each position is converted to normalized coordinates, mapped to
+/// `pos * scale + offset`, clamped to the range [-1.0, 1.0] and written back,
+/// rounding to the nearest coordinate. Colors are left untouched.
+pub fn transform(points: &mut [Point], scale: [f32; 2], offset: [f32; 2]) {
+    for point in points {
+        for (i, coord) in point.pos.iter_mut().enumerate() {
+            let normalized = normalized_from_coord(*coord) * scale[i] + offset[i];
+            *coord = coord_from_normalized_rounded(normalized);
+        }
+    }
+}
+
+/// The bounding box of `points` in normalized coordinates, as `(min, max)`.
+///
+/// If `ignore_blank` is set, blank points are left out, as they are not
+/// visible. Returns `None` if there are no points to bound.
+pub fn bounds(points: &[Point], ignore_blank: bool) -> Option<([f32; 2], [f32; 2])> {
+    let mut visible = points.iter().filter(|p| !(ignore_blank && p.is_blank()));
+    let first = visible.next()?;
+    let (min, max) = visible.fold((first.pos, first.pos), |(min, max), p| {
+        (
+            [min[0].min(p.pos[0]), min[1].min(p.pos[1])],
+            [max[0].max(p.pos[0]), max[1].max(p.pos[1])],
+        )
+    });
+    let normalized = |[x, y]: Position| [normalized_from_coord(x), normalized_from_coord(y)];
+    Some((normalized(min), normalized(max)))
+}
+
+/// Smooth a path by fitting a Catmull-Rom spline through its positions.
+///
+/// `subdivisions` points are inserted between each pair of consecutive lit
+/// points, sampled from the spline in normalized space and clamped to the
+/// range [-1.0, 1.0]. Colors are linearly interpolated between the pair. All
+/// input points are kept, so endpoints and corners are preserved.
+///
+/// Blank points act as path breaks: they are passed through untouched, and
+/// the spline is fit separately through each run of lit points between them.
+#[cfg(feature = "alloc")]
+pub fn smooth_catmull_rom(points: &[Point], subdivisions: usize) -> Vec<Point> {
+    let mut smoothed = Vec::with_capacity(points.len() * (subdivisions + 1));
+    let mut rest = points;
+    while !rest.is_empty() {
+        // Pass blank points through, then smooth the following lit run.
+        let blanks = rest.iter().take_while(|p| p.is_blank()).count();
+        smoothed.extend_from_slice(&rest[..blanks]);
+        rest = &rest[blanks..];
+        let lit = rest.iter().take_while(|p| !p.is_blank()).count();
+        let (run, tail) = rest.split_at(lit);
+        smooth_run(run, subdivisions, &mut smoothed);
+        rest = tail;
+    }
+    smoothed
+}
+
+/// Smooth a single run of lit points, appending the result to `out`.
+#[cfg(feature = "alloc")]
+fn smooth_run(run: &[Point], subdivisions: usize, out: &mut Vec<Point>) {
+    let pos = |i: usize| run[i].to_normalized().0;
+    for i in 0..run.len() {
+        out.push(run[i]);
+        if i + 1 == run.len() {
+            break;
+        }
+        // Duplicate the endpoints to provide the outer control points.
+        let [p0, p1, p2, p3] = [i.saturating_sub(1), i, i + 1, (i + 2).min(run.len() - 1)].map(pos);
+        for k in 1..=subdivisions {
+            let t = k as f32 / (subdivisions + 1) as f32;
+            let (t2, t3) = (t * t, t * t * t);
+            let xy = [0, 1].map(|j| {
+                let v = 0.5
+                    * (2.0 * p1[j]
+                        + (p2[j] - p0[j]) * t
+                        + (2.0 * p0[j] - 5.0 * p1[j] + 4.0 * p2[j] - p3[j]) * t2
+                        + (3.0 * p1[j] - p0[j] - 3.0 * p2[j] + p3[j]) * t3);
+                coord_from_normalized_rounded(v)
+            });
+            out.push(run[i].lerp(&run[i + 1], t).with_pos(xy));
+        }
+    }
+}
+
+/// Linearly interpolate between two `u16` values, rounding to the nearest.
+fn lerp_u16(a: u16, b: u16, t: f32) -> u16 {
+    let (a, b) = (a as f32, b as f32);
+    (a + (b - a) * t + 0.5) as u16
+}
+
 /// Produce a `Point`-compatible coordinate from a normalized coordinate.
 pub fn coord_from_normalized(coord_norm: f32) -> u16 {
-    let normalized = coord_norm.max(-1.0).min(1.0);
+    let normalized = coord_norm.clamp(-1.0, 1.0);
     let scaled = ((normalized + 1.0) / 2.0) * Point::MAX_COORD as f32;
     scaled as u16
 }
 
+/// Like [`coord_from_normalized`], but rounding to the nearest coordinate.
+fn coord_from_normalized_rounded(coord_norm: f32) -> u16 {
+    let normalized = coord_norm.clamp(-1.0, 1.0);
+    // Values are non-negative, so adding 0.5 and truncating rounds to nearest.
+    ((normalized + 1.0) / 2.0 * Point::MAX_COORD as f32 + 0.5) as u16
+}
+
 /// Produce a `Point`-compatible color value from a normalized color value.
 pub fn color_from_normalized(color_norm: f32) -> u16 {
-    let normalized = color_norm.max(0.0).min(1.0);
+    let normalized = color_norm.clamp(0.0, 1.0);
     let scaled = normalized * Point::MAX_COLOR as f32;
     scaled as u16
 }
 
+/// Produce a `Point`-compatible coordinate from a normalized coordinate,
+/// returning an error if it lies outside of the range [-1.0, 1.0].
+pub fn coord_from_normalized_checked(coord_norm: f32) -> Result<u16, OutOfRange> {
+    if !(-1.0..=1.0).contains(&coord_norm) {
+        return Err(OutOfRange(coord_norm));
+    }
+    Ok(coord_from_normalized(coord_norm))
+}
+
+/// Produce a `Point`-compatible color value from a normalized color value,
+/// returning an error if it lies outside of the range [0.0, 1.0].
+pub fn color_from_normalized_checked(color_norm: f32) -> Result<u16, OutOfRange> {
+    if !(0.0..=1.0).contains(&color_norm) {
+        return Err(OutOfRange(color_norm));
+    }
+    Ok(color_from_normalized(color_norm))
+}
+
+/// Produce a `Point`-compatible color value from an 8-bit color value.
+///
+/// The 8 bits are replicated into the low bits, i.e. `(c << 4) | (c >> 4)`, so
+/// that `0` maps to `0`, `255` maps to `MAX_COLOR` and the scaling is within
+/// one step of `c * 0xFFF / 0xFF` across the range.
+pub const fn color_from_rgb8(color: u8) -> u16 {
+    let c = color as u16;
+    (c << 4) | (c >> 4)
+}
+
+/// Convert a color from HSV to normalized RGB, e.g. for hue cycling effects.
+///
+/// The hue `h` is in degrees in the range [0.0, 360.0), with values outside
+/// the range wrapped around. Saturation `s` and value `v` are clamped to the
+/// range [0.0, 1.0]. The result is suitable for [`Point::from_normalized`].
+pub fn rgb_from_hsv(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let s = s.clamp(0.0, 1.0);
+    let v = v.clamp(0.0, 1.0);
+    let mut h = h % 360.0;
+    if h < 0.0 {
+        h += 360.0;
+    }
+    // Tiny negative hues round up to exactly 360.0 when wrapped.
+    if h >= 360.0 {
+        h -= 360.0;
+    }
+    let h = h / 60.0;
+    // Truncation floors the non-negative hue, selecting the sector.
+    let sector = h as u32;
+    let f = h - sector as f32;
+    let p = v * (1.0 - s);
+    let q = v * (1.0 - s * f);
+    let t = v * (1.0 - s * (1.0 - f));
+    match sector {
+        0 => [v, t, p],
+        1 => [q, v, p],
+        2 => [p, v, t],
+        3 => [p, q, v],
+        4 => [t, p, v],
+        _ => [v, p, q],
+    }
+}
+
 /// Produce a normalized coordinate from a `Point`-compatible coordinate.
 pub fn normalized_from_coord(coord: u16) -> f32 {
     (coord as f32 / Point::MAX_COORD as f32) * 2.0 - 1.0
@@ -132,6 +722,30 @@ mod tests {
         assert_eq!(p.rgb[2], 0);
     }
 
+    #[test]
+    fn test_point_try_new() {
+        let max = Point::MAX_COORD;
+        assert!(Point::try_new([max, max], [max, max, max]).is_ok());
+        assert!(Point::try_new([0, 0], [0, 0, 0]).unwrap().is_valid());
+
+        let over = 0x1000;
+        assert_eq!(Point::try_new([over, 0], [0; 3]), Err(PointError::X(over)));
+        assert_eq!(Point::try_new([0, over], [0; 3]), Err(PointError::Y(over)));
+        assert_eq!(
+            Point::try_new([0; 2], [over, 0, 0]),
+            Err(PointError::Red(over))
+        );
+        assert_eq!(
+            Point::try_new([0; 2], [0, over, 0]),
+            Err(PointError::Green(over))
+        );
+        assert_eq!(
+            Point::try_new([0; 2], [0, 0, over]),
+            Err(PointError::Blue(over))
+        );
+        assert!(!Point::new([0; 2], [0, 0, over]).is_valid());
+    }
+
     #[test]
     fn test_normalization_functions() {
         // Test coordinate normalization
@@ -195,6 +809,444 @@ mod tests {
         assert!((restored.rgb[2] as i32 - original.rgb[2] as i32).abs() <= 1);
     }
 
+    #[test]
+    fn test_lerp() {
+        let a = Point::new([0, 0], [0, Point::MAX_COLOR, 0x123]);
+        let b = Point::new([Point::MAX_COORD; 2], [Point::MAX_COLOR, 0, 0x456]);
+        assert_eq!(a.lerp(&b, 0.0), a);
+        assert_eq!(a.lerp(&b, 1.0), b);
+
+        // `t` is clamped.
+        assert_eq!(a.lerp(&b, -1.0), a);
+        assert_eq!(a.lerp(&b, 2.0), b);
+
+        // The midpoint lands within one unit of the center.
+        let mid = a.lerp(&b, 0.5);
+        for coord in mid.pos {
+            assert!((coord as i32 - 0x800).abs() <= 1);
+        }
+        assert!((mid.rgb[0] as i32 - 0x800).abs() <= 1);
+        assert!((mid.rgb[1] as i32 - 0x800).abs() <= 1);
+    }
+
+    #[test]
+    fn test_normalized_checked() {
+        // In range.
+        assert_eq!(
+            coord_from_normalized_checked(0.5),
+            Ok(coord_from_normalized(0.5))
+        );
+        assert_eq!(
+            color_from_normalized_checked(0.5),
+            Ok(color_from_normalized(0.5))
+        );
+
+        // Exactly at the bounds.
+        assert_eq!(coord_from_normalized_checked(-1.0), Ok(0));
+        assert_eq!(coord_from_normalized_checked(1.0), Ok(Point::MAX_COORD));
+        assert_eq!(color_from_normalized_checked(0.0), Ok(0));
+        assert_eq!(color_from_normalized_checked(1.0), Ok(Point::MAX_COLOR));
+
+        // Out of range.
+        assert_eq!(coord_from_normalized_checked(-1.5), Err(OutOfRange(-1.5)));
+        assert_eq!(coord_from_normalized_checked(1.01), Err(OutOfRange(1.01)));
+        assert_eq!(color_from_normalized_checked(-0.1), Err(OutOfRange(-0.1)));
+        assert_eq!(color_from_normalized_checked(2.0), Err(OutOfRange(2.0)));
+        assert!(coord_from_normalized_checked(f32::NAN).is_err());
+        assert!(color_from_normalized_checked(f32::NAN).is_err());
+    }
+
+    #[test]
+    fn test_from_normalized_checked() {
+        let (pos, rgb) = ([-1.0, 0.25], [0.0, 0.5, 1.0]);
+        assert_eq!(
+            Point::from_normalized_checked(pos, rgb),
+            Ok(Point::from_normalized(pos, rgb))
+        );
+        assert_eq!(
+            Point::from_normalized_checked([0.0, 1.5], rgb),
+            Err(OutOfRange(1.5))
+        );
+        assert_eq!(
+            Point::from_normalized_checked(pos, [0.0, 0.5, -0.5]),
+            Err(OutOfRange(-0.5))
+        );
+    }
+
+    #[test]
+    fn test_bounds() {
+        let lit = [0xfff, 0, 0];
+        let points = [
+            Point::new([0, Point::CENTER_COORD], lit),
+            Point::new([Point::MAX_COORD, Point::MAX_COORD], lit),
+            Point::new(Point::CENTER_POS, lit),
+            Point::new([Point::CENTER_COORD, 0x400], [0, 0, 0]),
+        ];
+        let (min, max) = bounds(&points, false).unwrap();
+        assert_eq!(min, [-1.0, normalized_from_coord(0x400)]);
+        assert_eq!(max, [1.0, 1.0]);
+
+        // The blank point no longer extends the box downwards.
+        let (min, max) = bounds(&points, true).unwrap();
+        assert_eq!(min, [-1.0, normalized_from_coord(Point::CENTER_COORD)]);
+        assert_eq!(max, [1.0, 1.0]);
+
+        assert_eq!(bounds(&[], false), None);
+        assert_eq!(bounds(&points[3..], true), None);
+        assert!(bounds(&points[3..], false).is_some());
+    }
+
+    #[test]
+    fn test_transform() {
+        let rgb = [0x123, 0x456, 0x789];
+        let mut points = [Point::new(Point::CENTER_POS, rgb)];
+
+        // Scaling about the center leaves a centered point in place.
+        transform(&mut points, [0.5, 0.5], [0.0, 0.0]);
+        assert_eq!(points[0], Point::new(Point::CENTER_POS, rgb));
+
+        // Translating by half the width moves the point halfway to the edge.
+        transform(&mut points, [1.0, 1.0], [0.5, 0.0]);
+        let x = points[0].pos[0];
+        assert!(
+            (x as i32 - coord_from_normalized(0.5) as i32).abs() <= 1,
+            "{x:#x}"
+        );
+        assert_eq!(points[0].pos[1], Point::CENTER_COORD);
+        assert_eq!(points[0].rgb, rgb);
+
+        // Results are clamped.
+        let mut points = [Point::new([0, Point::MAX_COORD], rgb)];
+        transform(&mut points, [2.0, 2.0], [0.0, 0.0]);
+        assert_eq!(points[0].pos, [0, Point::MAX_COORD]);
+        transform(&mut points, [1.0, 1.0], [5.0, -5.0]);
+        assert_eq!(points[0].pos, [Point::MAX_COORD, 0]);
+    }
+
+    #[test]
+    fn test_luminance() {
+        let green = Point::new(Point::CENTER_POS, [0, Point::MAX_COLOR, 0]);
+        assert!((green.luminance() - 0.7152).abs() < 1e-6);
+        assert!(!green.is_blank());
+
+        let white = green.with_color([Point::MAX_COLOR; 3]);
+        assert!((white.luminance() - 1.0).abs() < 1e-6);
+
+        assert_eq!(Point::CENTER_BLANK.luminance(), 0.0);
+        assert!(Point::CENTER_BLANK.is_blank());
+        assert!(green.blanked().is_blank());
+    }
+
+    #[test]
+    fn test_with_color_and_pos() {
+        let point = Point::new([0x123, 0x456], [0xFFF, 0x800, 0x1]);
+        let recolored = point.with_color([1, 2, 3]);
+        assert_eq!(recolored.pos, point.pos);
+        assert_eq!(recolored.rgb, [1, 2, 3]);
+        let moved = point.with_pos([7, 8]);
+        assert_eq!(moved.pos, [7, 8]);
+        assert_eq!(moved.rgb, point.rgb);
+    }
+
+    #[test]
+    fn test_clamp() {
+        let point = Point::new([0x1FFF, 0x123], [0x2000, 0x456, 0xFFF]);
+        assert!(!point.is_valid());
+        let clamped = point.clamp();
+        assert_eq!(clamped, Point::new([0xFFF, 0x123], [0xFFF, 0x456, 0xFFF]));
+        assert!(clamped.is_valid());
+        assert_eq!(clamped.clamp(), clamped);
+    }
+
+    #[test]
+    fn test_blanked() {
+        let point = Point::new([0x123, 0x456], [0xFFF, 0x800, 0x1]);
+        let blanked = point.blanked();
+        assert_eq!(blanked.pos, point.pos);
+        assert_eq!(blanked.rgb, Point::BLANK);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_distance() {
+        let a = Point::new([0x123, 0x456], [0xFFF; 3]);
+        assert_eq!(a.distance(&a), 0.0);
+        assert_eq!(a.distance(&a.blanked()), 0.0);
+
+        let corner = Point::new([0, 0], Point::BLANK);
+        let opposite = Point::new([Point::MAX_COORD; 2], Point::BLANK);
+        let expected = 2.0 * std::f32::consts::SQRT_2;
+        assert!((corner.distance(&opposite) - expected).abs() < 1e-6);
+        assert_eq!(corner.distance(&opposite), opposite.distance(&corner));
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_line() {
+        let (start, end) = ([-0.5, -0.5], [0.5, 0.5]);
+        let points = line(start, end, [1.0, 0.0, 1.0], 5);
+        assert_eq!(points.len(), 7);
+        let (first, last) = (points[0], points[points.len() - 1]);
+        assert_eq!(first.rgb, Point::BLANK);
+        assert_eq!(last.rgb, Point::BLANK);
+        assert_eq!(first.pos, Point::from_normalized(start, [0.0; 3]).pos);
+        assert_eq!(last.pos, Point::from_normalized(end, [0.0; 3]).pos);
+
+        // The lit points trace the diagonal from start to end.
+        let lit = &points[1..points.len() - 1];
+        assert_eq!(lit[0].pos, first.pos);
+        assert_eq!(lit[lit.len() - 1].pos, last.pos);
+        for pair in lit.windows(2) {
+            assert_eq!(pair[0].rgb, [Point::MAX_COLOR, 0, Point::MAX_COLOR]);
+            assert_eq!(pair[0].pos[0], pair[0].pos[1]);
+            assert!(pair[0].pos[0] < pair[1].pos[0]);
+        }
+
+        // Degenerate step counts.
+        assert_eq!(line(start, end, [1.0; 3], 0).len(), 2);
+        let points = line(start, end, [1.0; 3], 1);
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[1].pos, first.pos);
+    }
+
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn test_join_segments() {
+        let rgb = [Point::MAX_COLOR; 3];
+        let a = vec![Point::new([0, 0], rgb), Point::new([100, 0], rgb)];
+        let b = vec![Point::new([400, 300], rgb), Point::new([500, 300], rgb)];
+        let segments = [a.clone(), vec![], b.clone()];
+
+        let points = join_segments(&segments, 4, false);
+        assert_eq!(points.len(), 8);
+        assert_eq!(points[..2], a[..]);
+        assert_eq!(points[6..], b[..]);
+        let blanks = &points[2..6];
+        assert!(blanks.iter().all(Point::is_blank));
+        assert_eq!(blanks[1].pos, [250, 150]);
+        assert_eq!(blanks[3].pos, b[0].pos);
+
+        // Optionally blank the ends too.
+        let points = join_segments(&segments, 2, true);
+        assert_eq!(points.len(), 10);
+        assert_eq!(points[..2], [a[0].blanked(); 2]);
+        assert_eq!(points[8..], [b[1].blanked(); 2]);
+
+        // Without blank points, segments are simply concatenated.
+        assert_eq!(join_segments(&segments, 0, true), [a, b].concat());
+        assert!(join_segments(&[], 3, true).is_empty());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_polygon_square() {
+        let rotation = core::f32::consts::FRAC_PI_4;
+        let points = polygon(4, 0.5, rotation, [0.0, 1.0, 0.0], 1);
+        assert_eq!(points.len(), 5);
+        assert_eq!(points[0], points[4]);
+        for (i, a) in points[..4].iter().enumerate() {
+            assert_eq!(a.rgb, [0, Point::MAX_COLOR, 0]);
+            for b in &points[i + 1..4] {
+                assert_ne!(a.pos, b.pos);
+            }
+        }
+        // All vertices are equidistant from the center.
+        for point in &points {
+            let distance = point.distance(&Point::CENTER_BLANK);
+            assert!((distance - 0.5).abs() < 1e-3);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_polygon_points_per_edge() {
+        let points = polygon(3, 1.0, 0.0, [1.0; 3], 10);
+        assert_eq!(points.len(), 31);
+        assert_eq!(points[0], points[30]);
+        assert_eq!(
+            points[0].pos,
+            Point::from_normalized([1.0, 0.0], [0.0; 3]).pos
+        );
+        assert!(polygon(2, 1.0, 0.0, [1.0; 3], 10).is_empty());
+        assert_eq!(polygon(3, 1.0, 0.0, [1.0; 3], 0).len(), 4);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_color_profile_identity() {
+        let profile = ColorProfile::default();
+        for color in [0, 1, 0x400, 0x7FF, 0x800, 0xC00, 0xFFE, 0xFFF] {
+            let point = Point::new([0x100, 0x200], [color, color / 2, color / 3]);
+            assert_eq!(profile.apply(point), point);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_color_profile() {
+        let point = Point::new([0x100, 0x200], [0x800, 0x800, 0x800]);
+
+        // Gamma > 1 darkens midtones, but preserves black and full intensity.
+        let profile = ColorProfile::default().with_gamma([2.0; 3]);
+        let corrected = profile.apply(point);
+        assert_eq!(corrected.pos, point.pos);
+        assert!(corrected.rgb.iter().all(|&c| c < 0x800));
+        let extremes = Point::new([0; 2], [0, Point::MAX_COLOR, 0]);
+        assert_eq!(profile.apply(extremes), extremes);
+
+        // Gain scales each channel independently, clamping at `MAX_COLOR`.
+        let profile = ColorProfile::default().with_gain([1.0, 0.5, 4.0]);
+        let corrected = profile.apply(point);
+        assert_eq!(corrected.rgb[0], 0x800);
+        assert!((corrected.rgb[1] as i32 - 0x400).abs() <= 1);
+        assert_eq!(corrected.rgb[2], Point::MAX_COLOR);
+    }
+
+    #[test]
+    fn test_from_normalized_rgb8() {
+        let point = Point::from_normalized_rgb8([0.0, 1.0], [255, 0, 128]);
+        assert_eq!(point.pos, [Point::MAX_COORD / 2, Point::MAX_COORD]);
+        assert_eq!(point.rgb, [Point::MAX_COLOR, 0, 0x808]);
+        for c in 0..=u8::MAX {
+            let exact = c as u32 * Point::MAX_COLOR as u32 / u8::MAX as u32;
+            assert!(color_from_rgb8(c).abs_diff(exact as u16) <= 1, "{c}");
+        }
+    }
+
+    #[test]
+    fn test_rgb_from_hsv() {
+        assert_eq!(rgb_from_hsv(0.0, 1.0, 1.0), [1.0, 0.0, 0.0]);
+        assert_eq!(rgb_from_hsv(120.0, 1.0, 1.0), [0.0, 1.0, 0.0]);
+        assert_eq!(rgb_from_hsv(240.0, 1.0, 1.0), [0.0, 0.0, 1.0]);
+        assert_eq!(rgb_from_hsv(60.0, 1.0, 1.0), [1.0, 1.0, 0.0]);
+        assert_eq!(rgb_from_hsv(0.0, 0.0, 1.0), [1.0, 1.0, 1.0]);
+        assert_eq!(rgb_from_hsv(200.0, 0.0, 0.5), [0.5, 0.5, 0.5]);
+        assert_eq!(rgb_from_hsv(90.0, 1.0, 0.0), [0.0, 0.0, 0.0]);
+
+        // Hues wrap around.
+        assert_eq!(rgb_from_hsv(360.0, 1.0, 1.0), [1.0, 0.0, 0.0]);
+        assert_eq!(rgb_from_hsv(-120.0, 1.0, 1.0), [0.0, 0.0, 1.0]);
+        assert_eq!(rgb_from_hsv(480.0, 1.0, 1.0), [0.0, 1.0, 0.0]);
+        assert_eq!(rgb_from_hsv(-1e-6, 1.0, 1.0), [1.0, 0.0, 0.0]);
+        let point = Point::from_hsv([0.0, 0.0], -1e-6, 1.0, 1.0);
+        assert_eq!(point.rgb, [Point::MAX_COLOR, 0, 0]);
+
+        let point = Point::from_hsv([0.0, 0.0], 240.0, 1.0, 1.0);
+        assert_eq!(point.rgb, [0, 0, Point::MAX_COLOR]);
+    }
+
+    #[test]
+    fn test_mono() {
+        let point = Point::mono([0.0, 0.0], 0.5, [1.0, 1.0, 1.0]);
+        assert_eq!(point.pos, [coord_from_normalized(0.0); 2]);
+        for c in point.rgb {
+            assert!(c.abs_diff(Point::MAX_COLOR / 2) <= 1, "{c:#x}");
+        }
+
+        let point = Point::mono([0.0, 0.0], 0.5, [1.0, 0.0, 0.5]);
+        assert_eq!(point, Point::from_normalized([0.0, 0.0], [0.5, 0.0, 0.25]));
+        assert!(Point::mono([0.0, 0.0], 0.0, [1.0, 1.0, 1.0]).is_blank());
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_smooth_catmull_rom() {
+        let color = [0xFFF, 0x800, 0];
+        let points = [
+            Point::new([0x100, 0x100], color),
+            Point::new([0x800, 0xF00], color),
+            Point::new([0xF00, 0x100], color),
+        ];
+        let smoothed = smooth_catmull_rom(&points, 4);
+        assert_eq!(smoothed.len(), 3 + 2 * 4);
+        assert_eq!(smoothed[0], points[0]);
+        assert_eq!(smoothed[5], points[1]);
+        assert_eq!(smoothed[10], points[2]);
+        assert!(smoothed.iter().all(|p| p.rgb == color));
+        // The curve bulges beyond the straight line towards the middle point.
+        let line_y = lerp_u16(0x100, 0xF00, 0.8);
+        assert!(smoothed[4].pos[1] > line_y);
+
+        // No subdivisions leaves the path untouched.
+        assert_eq!(smooth_catmull_rom(&points, 0), points);
+
+        // Paths are not smoothed across blank points.
+        let blank = Point::new([0x800, 0x800], Point::BLANK);
+        let path = [points[0], points[1], blank, points[2]];
+        let smoothed = smooth_catmull_rom(&path, 2);
+        assert_eq!(smoothed.len(), 4 + 2);
+        assert_eq!(smoothed[3], points[1]);
+        assert_eq!(&smoothed[4..], &[blank, points[2]]);
+        assert!(smooth_catmull_rom(&[], 2).is_empty());
+    }
+
+    #[test]
+    fn test_tuple_conversions() {
+        let point = Point::from(([1, 2], [3, 4, 5]));
+        assert_eq!(point.pos, [1, 2]);
+        assert_eq!(point.rgb, [3, 4, 5]);
+        let (pos, rgb): (Position, Rgb) = point.into();
+        assert_eq!((pos, rgb), ([1, 2], [3, 4, 5]));
+
+        let tuples = [([0, 0], [0; 3]), ([0xFFF, 0x800], [0xFFF, 0, 0])];
+        let points: [Point; 2] = tuples.map(Into::into);
+        assert_eq!(points[1], Point::new([0xFFF, 0x800], [0xFFF, 0, 0]));
+    }
+
+    #[test]
+    fn test_color_mask() {
+        let point = Point::new([0x100, 0x200], [0x123, 0x456, 0x789]);
+        let red = ColorMask::RED_ONLY.apply(point);
+        assert_eq!(red, Point::new(point.pos, [0x123, 0, 0]));
+        let green = ColorMask::GREEN_ONLY.apply(point);
+        assert_eq!(green.rgb, [0, 0x456, 0]);
+        let blue = ColorMask::BLUE_ONLY.apply(point);
+        assert_eq!(blue.rgb, [0, 0, 0x789]);
+        assert_eq!(ColorMask::ALL.apply(point), point);
+        assert_eq!(ColorMask::default(), ColorMask::ALL);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_encode_decode_all() {
+        let points: Vec<Point> = (0..40u16)
+            .map(|i| Point::new([i * 100, 0xFFF - i * 100], [i, i * 2, i * 3]))
+            .collect();
+        let bytes = encode_all(&points);
+        assert_eq!(bytes.len(), points.len() * Point::SIZE);
+        assert_eq!(decode_all(&bytes), Ok(points));
+        assert_eq!(decode_all(&[]), Ok(vec![]));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_iter_points() {
+        let points: Vec<Point> = (0..40u16)
+            .map(|i| Point::new([i * 100, 0xFFF - i * 100], [i, i * 2, i * 3]))
+            .collect();
+        let mut bytes = encode_all(&points);
+        let iter = iter_points(&bytes);
+        assert_eq!(iter.len(), points.len());
+        assert_eq!(iter.collect::<Vec<_>>(), decode_all(&bytes).unwrap());
+
+        // A trailing partial point is skipped.
+        bytes.extend_from_slice(&[0xAB; 7]);
+        assert!(iter_points(&bytes).eq(points));
+        assert_eq!(iter_points(&[0xAB; 7]).next(), None);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn test_decode_all_misaligned() {
+        let points = [Point::new([1, 2], [3, 4, 5]); 3];
+        let mut bytes = encode_all(&points);
+        bytes.extend_from_slice(&[0xAB; 7]);
+        assert_eq!(
+            decode_all(&bytes),
+            Err(DecodeError::TrailingBytes { remainder: 7 })
+        );
+    }
+
     #[test]
     fn test_bytes() {
         let point = Point::new([0x1234, 0x5678], [0x9ABC, 0xDEF0, 0x1234]);
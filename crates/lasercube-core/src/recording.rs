@@ -0,0 +1,177 @@
+//! A simple binary format for recording and replaying laser shows.
+//!
+//! Requires the `std` feature.
+//!
+//! ## Format
+//!
+//! All integers are little-endian.
+//!
+//! ```text
+//! Offset  Size  Description
+//! 0       4     Magic bytes (`LCRC`)
+//! 4       1     Format version
+//! 5       4     DAC rate (u32)
+//! 9       4     Frame count (u32)
+//! 13      ...   Frames
+//! ```
+//!
+//! Each frame is encoded as its message number (u8), frame number (u8), point
+//! count (u16) and then the points themselves, `Point::SIZE` bytes each.
+
+use crate::{point, Point, SampleData};
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+/// A recorded sequence of `SampleData` messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    /// The DAC rate at which the recording should be replayed.
+    pub dac_rate: u32,
+    /// The recorded messages, in the order they were sent.
+    pub frames: Vec<SampleData>,
+}
+
+/// Error types that can occur when reading or writing a recording.
+#[derive(Debug, Error)]
+pub enum RecordingError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Invalid magic bytes: {0:?}")]
+    InvalidMagic([u8; 4]),
+    #[error("Unsupported recording version: {0}")]
+    UnsupportedVersion(u8),
+    #[error("Too many {what} to record: {len}")]
+    TooLong { what: &'static str, len: usize },
+}
+
+impl Recording {
+    /// Magic bytes identifying a recording.
+    pub const MAGIC: [u8; 4] = *b"LCRC";
+    /// The current version of the recording format.
+    pub const VERSION: u8 = 1;
+
+    /// Write the recording to the given writer.
+    pub fn write<W: Write>(&self, w: &mut W) -> Result<(), RecordingError> {
+        let frame_count =
+            u32::try_from(self.frames.len()).map_err(|_| RecordingError::TooLong {
+                what: "frames",
+                len: self.frames.len(),
+            })?;
+        w.write_all(&Self::MAGIC)?;
+        w.write_all(&[Self::VERSION])?;
+        w.write_all(&self.dac_rate.to_le_bytes())?;
+        w.write_all(&frame_count.to_le_bytes())?;
+        for frame in &self.frames {
+            let point_count =
+                u16::try_from(frame.points.len()).map_err(|_| RecordingError::TooLong {
+                    what: "points",
+                    len: frame.points.len(),
+                })?;
+            w.write_all(&[frame.message_num, frame.frame_num])?;
+            w.write_all(&point_count.to_le_bytes())?;
+            w.write_all(&point::encode_all(&frame.points))?;
+        }
+        Ok(())
+    }
+
+    /// Read a recording from the given reader.
+    pub fn read<R: Read>(r: &mut R) -> Result<Recording, RecordingError> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if magic != Self::MAGIC {
+            return Err(RecordingError::InvalidMagic(magic));
+        }
+        let [version] = read_array(r)?;
+        if version != Self::VERSION {
+            return Err(RecordingError::UnsupportedVersion(version));
+        }
+        let dac_rate = u32::from_le_bytes(read_array(r)?);
+        let frame_count = u32::from_le_bytes(read_array(r)?);
+
+        // Don't trust the count for preallocation, as the input may be corrupt.
+        let mut frames = Vec::new();
+        for _ in 0..frame_count {
+            let [message_num, frame_num] = read_array(r)?;
+            let point_count = u16::from_le_bytes(read_array(r)?) as usize;
+            let mut bytes = vec![0u8; point_count * Point::SIZE];
+            r.read_exact(&mut bytes)?;
+            let points = point::decode_all(&bytes).expect("buffer is a multiple of Point::SIZE");
+            frames.push(SampleData {
+                message_num,
+                frame_num,
+                points,
+            });
+        }
+        Ok(Recording { dac_rate, frames })
+    }
+}
+
+/// Read exactly `N` bytes from the reader.
+fn read_array<R: Read, const N: usize>(r: &mut R) -> io::Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn test_recording() -> Recording {
+        let frames = (0..5u8)
+            .map(|i| SampleData {
+                message_num: i,
+                frame_num: i / 2,
+                points: (0..i as u16 * 30)
+                    .map(|j| Point::new([j, 0xFFF - j], [j, 0, 0xFFF]))
+                    .collect(),
+            })
+            .collect();
+        Recording {
+            dac_rate: 30_000,
+            frames,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let recording = test_recording();
+        let mut cursor = Cursor::new(Vec::new());
+        recording.write(&mut cursor).unwrap();
+        cursor.set_position(0);
+        let read = Recording::read(&mut cursor).unwrap();
+        assert_eq!(read, recording);
+        assert_eq!(cursor.position() as usize, cursor.get_ref().len());
+    }
+
+    #[test]
+    fn test_invalid_magic() {
+        let mut cursor = Cursor::new(b"NOPE\x01".to_vec());
+        assert!(matches!(
+            Recording::read(&mut cursor),
+            Err(RecordingError::InvalidMagic(magic)) if &magic == b"NOPE"
+        ));
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let mut bytes = Recording::MAGIC.to_vec();
+        bytes.push(Recording::VERSION + 1);
+        assert!(matches!(
+            Recording::read(&mut Cursor::new(bytes)),
+            Err(RecordingError::UnsupportedVersion(v)) if v == Recording::VERSION + 1
+        ));
+    }
+
+    #[test]
+    fn test_truncated() {
+        let mut bytes = Vec::new();
+        test_recording().write(&mut bytes).unwrap();
+        bytes.pop();
+        assert!(matches!(
+            Recording::read(&mut Cursor::new(bytes)),
+            Err(RecordingError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+}
@@ -1,8 +1,9 @@
+use crate::FirmwareVersion;
 use bitflags::bitflags;
 
 bitflags! {
     /// Status flags for the LaserCube device (byte 5 of the full info response).
-    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
     pub struct StatusFlags: u8 {
         /// Output is enabled
         const OUTPUT_ENABLED = 0b0000_0001;
@@ -26,7 +27,75 @@ bitflags! {
     }
 }
 
+/// Status flags decoded for a particular firmware version, along with the raw
+/// byte they were decoded from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodedStatus {
+    /// The raw status byte.
+    pub raw: u8,
+    /// Whether output is enabled.
+    pub output_enabled: bool,
+    /// Whether interlock is enabled.
+    pub interlock_enabled: bool,
+    /// Whether a temperature warning is active.
+    pub temperature_warning: bool,
+    /// Whether an over-temperature condition is active.
+    pub over_temperature: bool,
+    /// The packet errors count, always `0` for firmware version <= 0.12.
+    pub packet_errors: u8,
+}
+
 impl StatusFlags {
+    /// Encode status flags using the bit layout for the given firmware version.
+    ///
+    /// Packet errors are clamped to 15 and only encoded for firmware version
+    /// >= 0.13, as older firmware uses the upper bits for other flags.
+    pub fn encode(
+        fw_major: u8,
+        fw_minor: u8,
+        output: bool,
+        interlock: bool,
+        temp_warning: bool,
+        over_temp: bool,
+        packet_errors: u8,
+    ) -> Self {
+        let mut flags = Self::empty();
+        flags.set(Self::OUTPUT_ENABLED, output);
+        if has_v013_layout(FirmwareVersion::new(fw_major, fw_minor)) {
+            flags.set(Self::INTERLOCK_ENABLED_V013, interlock);
+            flags.set(Self::TEMPERATURE_WARNING_V013, temp_warning);
+            flags.set(Self::OVER_TEMPERATURE_V013, over_temp);
+            flags = flags.with_packet_errors(packet_errors.min(0x0F));
+        } else {
+            flags.set(Self::INTERLOCK_ENABLED_V012, interlock);
+            flags.set(Self::TEMPERATURE_WARNING_V012, temp_warning);
+            flags.set(Self::OVER_TEMPERATURE_V012, over_temp);
+        }
+        flags
+    }
+
+    /// Decode all flags for the given firmware version.
+    pub fn decode(self, fw_major: u8, fw_minor: u8) -> DecodedStatus {
+        self.decode_for(FirmwareVersion::new(fw_major, fw_minor))
+    }
+
+    /// Decode all flags for the given firmware version.
+    pub fn decode_for(self, version: FirmwareVersion) -> DecodedStatus {
+        let packet_errors = if has_v013_layout(version) {
+            self.packet_errors()
+        } else {
+            0
+        };
+        DecodedStatus {
+            raw: self.bits(),
+            output_enabled: self.output_enabled(),
+            interlock_enabled: self.interlock_enabled_for(version),
+            temperature_warning: self.temperature_warning_for(version),
+            over_temperature: self.over_temperature_for(version),
+            packet_errors,
+        }
+    }
+
     /// Get whether output is enabled.
     pub fn output_enabled(self) -> bool {
         self.contains(Self::OUTPUT_ENABLED)
@@ -34,7 +103,12 @@ impl StatusFlags {
 
     /// Get whether interlock is enabled, handling firmware version differences.
     pub fn interlock_enabled(self, fw_major: u8, fw_minor: u8) -> bool {
-        if fw_major > 0 || fw_minor >= 13 {
+        self.interlock_enabled_for(FirmwareVersion::new(fw_major, fw_minor))
+    }
+
+    /// Get whether interlock is enabled, handling firmware version differences.
+    pub fn interlock_enabled_for(self, version: FirmwareVersion) -> bool {
+        if has_v013_layout(version) {
             self.contains(Self::INTERLOCK_ENABLED_V013)
         } else {
             self.contains(Self::INTERLOCK_ENABLED_V012)
@@ -44,7 +118,13 @@ impl StatusFlags {
     /// Get whether there's a temperature warning, handling firmware version
     /// differences.
     pub fn temperature_warning(self, fw_major: u8, fw_minor: u8) -> bool {
-        if fw_major > 0 || fw_minor >= 13 {
+        self.temperature_warning_for(FirmwareVersion::new(fw_major, fw_minor))
+    }
+
+    /// Get whether there's a temperature warning, handling firmware version
+    /// differences.
+    pub fn temperature_warning_for(self, version: FirmwareVersion) -> bool {
+        if has_v013_layout(version) {
             self.contains(Self::TEMPERATURE_WARNING_V013)
         } else {
             self.contains(Self::TEMPERATURE_WARNING_V012)
@@ -54,7 +134,13 @@ impl StatusFlags {
     /// Get whether there's an over-temperature condition, handling firmware
     /// version differences.
     pub fn over_temperature(self, fw_major: u8, fw_minor: u8) -> bool {
-        if fw_major > 0 || fw_minor >= 13 {
+        self.over_temperature_for(FirmwareVersion::new(fw_major, fw_minor))
+    }
+
+    /// Get whether there's an over-temperature condition, handling firmware
+    /// version differences.
+    pub fn over_temperature_for(self, version: FirmwareVersion) -> bool {
+        if has_v013_layout(version) {
             self.contains(Self::OVER_TEMPERATURE_V013)
         } else {
             self.contains(Self::OVER_TEMPERATURE_V012)
@@ -63,11 +149,50 @@ impl StatusFlags {
 
     /// Get the packet errors count (firmware version >= 0.13 only).
     pub fn packet_errors(self) -> u8 {
-        if self.is_empty() {
-            0
-        } else {
-            (self.bits() & Self::PACKET_ERRORS_MASK.bits()) >> 4
-        }
+        (self.bits() & Self::PACKET_ERRORS_MASK.bits()) >> 4
+    }
+
+    /// Set the packet errors count (firmware version >= 0.13 only).
+    ///
+    /// Only the lower 4 bits of `count` are retained.
+    pub fn with_packet_errors(self, count: u8) -> Self {
+        let bits = self.bits() & !Self::PACKET_ERRORS_MASK.bits();
+        Self::from_bits_retain(bits | ((count & 0x0F) << 4))
+    }
+}
+
+/// Whether the firmware uses the status flag layout introduced in 0.13.
+fn has_v013_layout(version: FirmwareVersion) -> bool {
+    version >= FirmwareVersion::V0_13
+}
+
+impl core::fmt::Display for DecodedStatus {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let on_off = |b: bool| if b { "on" } else { "off" };
+        write!(
+            f,
+            "output={} interlock={} temp_warn={} over_temp={} errors={}",
+            on_off(self.output_enabled),
+            on_off(self.interlock_enabled),
+            on_off(self.temperature_warning),
+            on_off(self.over_temperature),
+            self.packet_errors,
+        )
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for StatusFlags {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.bits())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for StatusFlags {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bits = u8::deserialize(deserializer)?;
+        Ok(Self::from_bits_retain(bits))
     }
 }
 
@@ -91,6 +216,8 @@ mod tests {
         assert!(flags.interlock_enabled(0, 13));
         assert!(flags.interlock_enabled(1, 0));
         assert!(!flags.interlock_enabled(0, 12));
+        assert!(flags.interlock_enabled_for(FirmwareVersion::V0_13));
+        assert!(!flags.interlock_enabled_for(FirmwareVersion::new(0, 12)));
 
         // Test for older firmware
         let flags = StatusFlags::INTERLOCK_ENABLED_V012;
@@ -126,6 +253,30 @@ mod tests {
         assert!(flags.over_temperature(0, 12));
     }
 
+    #[test]
+    fn test_encode() {
+        let flags = StatusFlags::encode(0, 13, true, true, true, true, 2);
+        assert_eq!(flags.bits(), 0x2F);
+        let flags = StatusFlags::encode(0, 12, true, true, true, true, 2);
+        assert_eq!(flags.bits(), 0x39);
+        let flags = StatusFlags::encode(1, 0, false, true, false, false, 0);
+        assert_eq!(flags.bits(), 0x02);
+
+        // Packet errors are clamped to 4 bits.
+        let flags = StatusFlags::encode(1, 0, false, false, false, false, 200);
+        assert_eq!(flags.bits(), 0xF0);
+        assert_eq!(flags.packet_errors(), 15);
+
+        // Encoding round-trips through the version-aware accessors.
+        for (major, minor) in [(0, 12), (0, 13), (1, 0)] {
+            let flags = StatusFlags::encode(major, minor, false, true, false, true, 0);
+            assert!(!flags.output_enabled());
+            assert!(flags.interlock_enabled(major, minor));
+            assert!(!flags.temperature_warning(major, minor));
+            assert!(flags.over_temperature(major, minor));
+        }
+    }
+
     #[test]
     fn test_packet_errors() {
         let flags = StatusFlags::from_bits_truncate(0x50); // 0101_0000
@@ -133,5 +284,60 @@ mod tests {
 
         let flags = StatusFlags::empty();
         assert_eq!(flags.packet_errors(), 0);
+
+        let flags = StatusFlags::OUTPUT_ENABLED | StatusFlags::INTERLOCK_ENABLED_V013;
+        assert_eq!(flags.packet_errors(), 0);
+    }
+
+    #[test]
+    fn test_with_packet_errors() {
+        let flags = StatusFlags::OUTPUT_ENABLED | StatusFlags::OVER_TEMPERATURE_V013;
+        for count in 0..=15 {
+            let with_errors = flags.with_packet_errors(count);
+            assert_eq!(with_errors.packet_errors(), count);
+            // The lower flags are preserved.
+            assert_eq!(with_errors.bits() & 0x0F, flags.bits());
+            // Overwriting clears the previous count.
+            assert_eq!(with_errors.with_packet_errors(0), flags);
+        }
+        // Only the lower 4 bits of the count are retained.
+        assert_eq!(flags.with_packet_errors(0x13).packet_errors(), 3);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_raw_bits() {
+        let flags = StatusFlags::from_bits_retain(0x2F);
+        let json = serde_json::to_string(&flags).unwrap();
+        assert_eq!(json, "47");
+        assert_eq!(serde_json::from_str::<StatusFlags>(&json).unwrap(), flags);
+    }
+
+    #[test]
+    fn test_decode() {
+        // Output, temperature warning and 3 packet errors.
+        let flags = StatusFlags::from_bits_retain(0b0011_0101);
+        let status = flags.decode(0, 13);
+        assert_eq!(
+            status,
+            DecodedStatus {
+                raw: 0x35,
+                output_enabled: true,
+                interlock_enabled: false,
+                temperature_warning: true,
+                over_temperature: false,
+                packet_errors: 3,
+            }
+        );
+        assert_eq!(
+            status.to_string(),
+            "output=on interlock=off temp_warn=on over_temp=off errors=3"
+        );
+
+        // The upper bits are flags rather than packet errors for v0.12.
+        let status = flags.decode(0, 12);
+        assert!(status.over_temperature);
+        assert_eq!(status.packet_errors, 0);
+        assert_eq!(flags.decode_for(FirmwareVersion::new(0, 12)), status);
     }
 }
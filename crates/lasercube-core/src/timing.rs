@@ -0,0 +1,55 @@
+//! Helpers relating point counts to time at a given DAC rate.
+
+use core::time::Duration;
+
+/// The number of points rendered per frame at `dac_rate` points per second
+/// when drawing `fps` frames per second, rounded down.
+///
+/// Fewer points per frame reduces flicker at the cost of detail. Returns `0`
+/// if `fps` is not positive.
+pub fn points_per_frame(dac_rate: u32, fps: f32) -> usize {
+    if fps <= 0.0 {
+        return 0;
+    }
+    // A NaN `fps` yields NaN, which casts to `0`.
+    (dac_rate as f32 / fps) as usize
+}
+
+/// The time taken to render `point_count` points at `dac_rate` points per
+/// second.
+///
+/// Returns [`Duration::ZERO`] if `dac_rate` is `0`.
+pub fn frame_duration(dac_rate: u32, point_count: usize) -> Duration {
+    if dac_rate == 0 {
+        return Duration::ZERO;
+    }
+    let nanos = point_count as u128 * 1_000_000_000 / dac_rate as u128;
+    Duration::from_nanos(nanos.min(u64::MAX as u128) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_points_per_frame() {
+        assert_eq!(points_per_frame(30_000, 30.0), 1000);
+        assert_eq!(points_per_frame(30_000, 60.0), 500);
+        // Rounded down.
+        assert_eq!(points_per_frame(1000, 3.0), 333);
+        assert_eq!(points_per_frame(30_000, 0.0), 0);
+        assert_eq!(points_per_frame(30_000, -1.0), 0);
+        assert_eq!(points_per_frame(30_000, f32::NAN), 0);
+    }
+
+    #[test]
+    fn test_frame_duration() {
+        assert_eq!(
+            frame_duration(30_000, 1000),
+            Duration::from_nanos(33_333_333)
+        );
+        assert_eq!(frame_duration(1000, 500), Duration::from_millis(500));
+        assert_eq!(frame_duration(30_000, 0), Duration::ZERO);
+        assert_eq!(frame_duration(0, 1000), Duration::ZERO);
+    }
+}
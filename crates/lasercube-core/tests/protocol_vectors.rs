@@ -0,0 +1,100 @@
+//! Golden byte vectors pinning the little-endian wire format.
+//!
+//! The values mirror the fixtures used by the unit tests.
+#![cfg(feature = "alloc")]
+
+use lasercube_core::cmds::{Command, Response};
+use lasercube_core::{
+    point, ConnectionType, LaserInfo, LaserInfoHeader, Point, SampleData, StatusFlags,
+};
+use std::net::Ipv4Addr;
+
+fn laser_info() -> LaserInfo {
+    LaserInfo {
+        header: LaserInfoHeader {
+            fw_major: 0,
+            fw_minor: 13,
+            status: StatusFlags::from_bits_retain(0x2F),
+            dac_rate: 30_000,
+            max_dac_rate: 40_000,
+            rx_buffer_free: 5000,
+            rx_buffer_size: 6000,
+            battery_percent: 87,
+            temperature: 42,
+            model_number: 3,
+            conn_type: ConnectionType::Wifi,
+            serial_number: [0xde, 0xad, 0xbe, 0xef, 0x01, 0x02],
+            ip_addr: Ipv4Addr::new(10, 0, 0, 42),
+        },
+        model_name: "LaserCube".to_string(),
+    }
+}
+
+#[rustfmt::skip]
+const LASER_INFO_BYTES: [u8; 48] = [
+    0x77, 0x00, 0x00,             // Command echo, padding
+    0x00, 0x0d,                   // Firmware 0.13
+    0x2f,                         // Status flags
+    0x00, 0x00, 0x00, 0x00,       // Padding
+    0x30, 0x75, 0x00, 0x00,       // DAC rate 30000
+    0x40, 0x9c, 0x00, 0x00,       // Max DAC rate 40000
+    0x00,                         // Padding
+    0x88, 0x13,                   // RX buffer free 5000
+    0x70, 0x17,                   // RX buffer size 6000
+    0x57,                         // Battery 87%
+    0x2a,                         // Temperature 42
+    0x03,                         // Wifi
+    0xde, 0xad, 0xbe, 0xef, 0x01, 0x02, // Serial number
+    0x0a, 0x00, 0x00, 0x2a,       // IP 10.0.0.42
+    0x00,                         // Padding
+    0x03,                         // Model number
+    b'L', b'a', b's', b'e', b'r', b'C', b'u', b'b', b'e', 0x00, // Model name
+];
+
+#[test]
+fn test_laser_info_vector() {
+    let info = laser_info();
+    assert_eq!(info.to_bytes(), LASER_INFO_BYTES);
+    assert_eq!(LaserInfo::try_from(&LASER_INFO_BYTES[..]).unwrap(), info);
+    assert_eq!(
+        Response::try_from(&LASER_INFO_BYTES[..]).unwrap(),
+        Response::FullInfo(info)
+    );
+}
+
+#[rustfmt::skip]
+const SAMPLE_DATA_BYTES: [u8; 24] = [
+    0xa9, 0x00,                   // Command, padding
+    0x07,                         // Message number
+    0x01,                         // Frame number
+    0x23, 0x01, 0x56, 0x04,       // Position (0x123, 0x456)
+    0xff, 0x0f, 0x00, 0x08, 0x00, 0x00, // Color (0xfff, 0x800, 0)
+    0x00, 0x08, 0x00, 0x08,       // Position (0x800, 0x800)
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, // Blank
+];
+
+#[test]
+fn test_sample_data_vector() {
+    let points = vec![
+        Point::new([0x123, 0x456], [0xfff, 0x800, 0]),
+        Point::new([0x800, 0x800], [0, 0, 0]),
+    ];
+    let command = Command::SampleData(SampleData {
+        message_num: 7,
+        frame_num: 1,
+        points: points.clone(),
+    });
+    assert_eq!(command.to_bytes(), SAMPLE_DATA_BYTES);
+    let decoded = point::decode_all(&SAMPLE_DATA_BYTES[SampleData::HEADER_SIZE..]).unwrap();
+    assert_eq!(decoded, points);
+}
+
+#[test]
+fn test_buffer_free_vector() {
+    // 1000 free points in response to `GetRingbufferEmptySampleCount`.
+    let bytes = [0x8a, 0x00, 0xe8, 0x03];
+    let response = Response::try_from(&bytes[..]).unwrap();
+    assert_eq!(response, Response::BufferFree(1000));
+    assert_eq!(response.to_bytes(), bytes);
+    assert_eq!(Command::GetRingbufferEmptySampleCount.to_bytes(), [0x8a]);
+}
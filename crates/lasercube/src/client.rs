@@ -1,10 +1,41 @@
+use crate::metrics::{ClientMetrics, ClientMetricsSnapshot};
+use futures::{future::join_all, Stream, StreamExt};
 use lasercube_core::{
-    cmds::{Command, CommandType, Response, ResponseParseError},
-    port,
+    cmds::{
+        Command, CommandType, CommandValidationError, Response, ResponseParseError, ALIVE_PING,
+    },
+    frame::MessageCounter,
+    hex_preview, BufferState, LaserInfo, Point, Ports, SafetyState, SampleData, MAX_MESSAGE_SIZE,
+    MAX_POINTS_PER_MESSAGE,
 };
-use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::net::UdpSocket;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+use tracing::Instrument;
+
+/// The default duration to wait for a response to a command.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The default size of the buffer used to receive responses.
+///
+/// Responses are expected to be far smaller. A datagram filling the whole
+/// buffer may have been truncated, in which case a warning is logged.
+pub const MAX_RESPONSE_SIZE: usize = 1024;
+
+/// The maximum number of responses to other commands discarded while waiting
+/// for the response to a command, e.g. replies to other controllers on the
+/// same network.
+pub const MAX_DISCARDED_RESPONSES: usize = 8;
+
+/// The number of idle messages sent by [`Client::blank_output`] before
+/// disabling output.
+pub const IDLE_MESSAGES: usize = 3;
 
 /// Error types that can occur when interacting with a LaserCube device
 #[derive(Debug, Error)]
@@ -16,22 +47,111 @@ pub enum CommandError {
     #[error("Response parse error: {0}")]
     Parse(#[from] ResponseParseError),
     /// Received an unexpected response.
-    #[error("Unexpected response: expected command type {expected:?}, got {actual}")]
-    UnexpectedResponse { expected: CommandType, actual: u8 },
+    ///
+    /// Responses that could not be parsed are attached as
+    /// [`Response::Unknown`].
+    #[error("Unexpected response: expected command type {expected:?}, got {got:?}")]
+    UnexpectedResponse {
+        expected: CommandType,
+        got: Response,
+    },
+    /// Received a datagram from an address other than the target device.
+    #[error("Unexpected response from {0}")]
+    UnexpectedSource(SocketAddr),
+    /// The device reported a different state to the one requested.
+    #[error("State mismatch: requested {requested}, device reported {reported}")]
+    StateMismatch { requested: bool, reported: bool },
+    /// No response was received within the timeout.
+    #[error("Timed out after {0:?} waiting for a response")]
+    Timeout(Duration),
+    /// The command was rejected before sending.
+    #[error("Invalid command: {0}")]
+    Invalid(#[from] CommandValidationError),
 }
 
 /// A client for sending commands to a specific LaserCube device.
 #[derive(Debug)]
 pub struct Client {
     /// Socket for sending commands
-    socket: UdpSocket,
+    socket: Arc<UdpSocket>,
+    /// Socket for sending point data, possibly shared with `socket`
+    data_socket: Arc<UdpSocket>,
+    /// Local address the sockets are bound to, used for the keep-alive sockets
+    bind_ip: IpAddr,
     /// Target address for the device
-    target_addr: SocketAddrV4,
+    target_addr: SocketAddr,
+    /// Target address for the device's point data
+    data_addr: SocketAddr,
+    /// Target address for the device's alive port
+    alive_addr: SocketAddr,
+    /// Whether to disable output when the client is dropped
+    auto_disable: bool,
+    /// The size of the buffer used to receive responses
+    max_response_size: usize,
+    /// Traffic counters
+    metrics: ClientMetrics,
+    /// Span identifying the device, the parent of the client's operations
+    span: tracing::Span,
+    /// The last known output state, one of the `OUTPUT_*` constants
+    output_state: AtomicU8,
+}
+
+// Values of `Client::output_state` for unknown, disabled and enabled output.
+const OUTPUT_UNKNOWN: u8 = 0;
+const OUTPUT_DISABLED: u8 = 1;
+const OUTPUT_ENABLED: u8 = 2;
+
+/// Configuration for streaming points with [`Client::stream_points`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StreamConfig {
+    /// The maximum latency between sending a point and it being rendered.
+    ///
+    /// Limits how much of the device's buffer is kept filled.
+    pub max_latency_ms: u16,
+    /// The rate at which the device renders points, in points per second.
+    pub dac_rate: u32,
+    /// The total size of the device's buffer in points.
+    pub rx_buffer_size: u16,
+    /// Whether to re-send the most recent message if the point stream stalls.
+    ///
+    /// When enabled, if no new point arrives before the device is estimated
+    /// to have rendered all queued points, the last sent `SampleData` is sent
+    /// again to keep the beam steady rather than letting the buffer run dry.
+    pub repeat_last_on_underflow: bool,
+}
+
+impl StreamConfig {
+    /// The default maximum latency.
+    pub const DEFAULT_MAX_LATENCY_MS: u16 = 64;
+
+    /// Create a config from the device's info with the default max latency.
+    pub fn from_laser_info(info: &LaserInfo) -> Self {
+        Self {
+            max_latency_ms: Self::DEFAULT_MAX_LATENCY_MS,
+            dac_rate: info.header.dac_rate,
+            rx_buffer_size: info.header.rx_buffer_size,
+            repeat_last_on_underflow: false,
+        }
+    }
+
+    /// The number of points that may be buffered on the device without
+    /// exceeding the max latency.
+    ///
+    /// Returns the full buffer size if `dac_rate` is `0`.
+    pub fn max_buffered_points(&self) -> u16 {
+        if self.dac_rate == 0 {
+            return self.rx_buffer_size;
+        }
+        let points = self.dac_rate as u64 * self.max_latency_ms as u64 / 1_000;
+        points.min(self.rx_buffer_size as u64) as u16
+    }
 }
 
 impl Client {
     /// Create a new Client from a single target device IP (non-broadcast).
     ///
+    /// Binds one socket for the CMD port and another for the DATA port.
+    ///
     /// Returns a new Client or an error if the socket couldn't be created.
     ///
     /// # Example
@@ -61,60 +181,336 @@ impl Client {
     ///     Ok(())
     /// }
     /// ```
-    #[tracing::instrument]
     pub async fn new(bind_ip: IpAddr, target_ip: Ipv4Addr) -> Result<Self, CommandError> {
+        Self::with_target_ip(bind_ip, target_ip.into()).await
+    }
+
+    /// Create a new Client from a single target device IP, either IPv4 or IPv6.
+    ///
+    /// This is the same as [`Client::new`], but allows connecting to devices
+    /// reachable over IPv6. The `bind_ip` must be of the same address family
+    /// as the `target_ip`.
+    pub async fn with_target_ip(bind_ip: IpAddr, target_ip: IpAddr) -> Result<Self, CommandError> {
+        Self::new_with_ports(bind_ip, target_ip, Ports::default()).await
+    }
+
+    /// Create a new Client targeting the given ports on the device.
+    ///
+    /// This is the same as [`Client::with_target_ip`], but allows reaching
+    /// devices behind a proxy that remaps the standard ports.
+    #[tracing::instrument]
+    pub async fn new_with_ports(
+        bind_ip: IpAddr,
+        target_ip: IpAddr,
+        ports: Ports,
+    ) -> Result<Self, CommandError> {
         // Create a socket for CMD port communications
         let bind_addr = SocketAddr::new(bind_ip, 0); // Use ephemeral port
         tracing::debug!("Binding to UDP socket {bind_addr:?} for commands");
         let socket = UdpSocket::bind(bind_addr).await?;
-        // Set up the target address
-        let target_addr = SocketAddrV4::new(target_ip.into(), port::CMD);
-        // Create the client
-        let client = Client {
+        // Create a socket for DATA port communications
+        tracing::debug!("Binding to UDP socket {bind_addr:?} for data");
+        let data_socket = UdpSocket::bind(bind_addr).await?;
+        let (socket, data_socket) = (Arc::new(socket), Arc::new(data_socket));
+        Ok(Self::from_sockets(
             socket,
-            target_addr,
-        };
-        Ok(client)
+            data_socket,
+            bind_ip,
+            target_ip,
+            ports,
+        ))
+    }
+
+    /// Create a new Client from an already bound socket.
+    ///
+    /// Useful for applications that configure their own socket options, e.g.
+    /// buffer sizes or binding to a specific interface.
+    ///
+    /// The socket is used for both the CMD and DATA ports, so responses to
+    /// commands and point data arrive on the same socket. Avoid awaiting
+    /// command responses while also awaiting buffer size responses (see
+    /// [`Client::recv_buffer_free`]), as either may receive the other's reply.
+    pub fn with_socket(socket: UdpSocket, target_ip: Ipv4Addr) -> Self {
+        let bind_ip = socket
+            .local_addr()
+            .map_or(Ipv4Addr::UNSPECIFIED.into(), |addr| addr.ip());
+        let socket = Arc::new(socket);
+        Self::from_sockets(
+            socket.clone(),
+            socket,
+            bind_ip,
+            target_ip.into(),
+            Ports::default(),
+        )
+    }
+
+    /// Create a client from its sockets, targeting the given ports.
+    fn from_sockets(
+        socket: Arc<UdpSocket>,
+        data_socket: Arc<UdpSocket>,
+        bind_ip: IpAddr,
+        target_ip: IpAddr,
+        ports: Ports,
+    ) -> Self {
+        Client {
+            socket,
+            data_socket,
+            bind_ip,
+            target_addr: SocketAddr::new(target_ip, ports.cmd),
+            data_addr: SocketAddr::new(target_ip, ports.data),
+            alive_addr: SocketAddr::new(target_ip, ports.alive),
+            auto_disable: false,
+            max_response_size: MAX_RESPONSE_SIZE,
+            metrics: ClientMetrics::default(),
+            span: device_span(target_ip),
+            output_state: AtomicU8::new(OUTPUT_UNKNOWN),
+        }
+    }
+
+    /// The size of the buffer used to receive responses.
+    ///
+    /// Defaults to [`MAX_RESPONSE_SIZE`].
+    pub fn max_response_size(&self) -> usize {
+        self.max_response_size
+    }
+
+    /// Set the size of the buffer used to receive responses.
+    ///
+    /// Larger responses are truncated, and a warning is logged.
+    pub fn set_max_response_size(&mut self, size: usize) {
+        self.max_response_size = size;
+    }
+
+    /// The local address the client's command socket is bound to.
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+
+    /// The address of the device's CMD port that commands are sent to.
+    pub fn target_addr(&self) -> SocketAddr {
+        self.target_addr
+    }
+
+    /// A snapshot of the counters tracking this client's traffic.
+    pub fn metrics(&self) -> ClientMetricsSnapshot {
+        self.metrics.snapshot()
+    }
+
+    /// The span identifying the target device.
+    ///
+    /// All of the client's operations are traced within this span, which
+    /// records the device's IP address, and its serial number once known.
+    pub fn span(&self) -> &tracing::Span {
+        &self.span
+    }
+
+    /// Record the device's serial number on the client's span.
+    fn record_serial(&self, serial_number: [u8; 6]) {
+        self.span
+            .record("serial", tracing::field::debug(serial_number));
+    }
+
+    /// Disable the laser's output when this client is dropped.
+    ///
+    /// On drop, a `SetOutput(false)` command is sent without waiting for a
+    /// response. This is best-effort only: the send may fail (e.g. if the
+    /// socket is not yet known to be writable because nothing has been sent
+    /// yet) or the packet may be lost, and nothing is sent if the process
+    /// exits without dropping the client (e.g. when killed).
+    pub fn arm_auto_disable(&mut self) {
+        self.auto_disable = true;
     }
 
     /// Send a command to the LaserCube and wait for a response.
     ///
-    /// This method will await until a response is received.
+    /// Waits up to [`DEFAULT_TIMEOUT`] for the response. See
+    /// [`Client::send_command_timeout`].
+    pub async fn send_command(&self, command: Command) -> Result<Response, CommandError> {
+        self.send_command_timeout(command, DEFAULT_TIMEOUT).await
+    }
+
+    /// Send a command to the LaserCube and wait up to `timeout` for a response.
+    ///
+    /// The timeout applies only to receiving the response, not to sending the
+    /// command.
+    ///
+    /// Responses echoing a different command, and datagrams from any IP other
+    /// than the target device's, are discarded, up to
+    /// [`MAX_DISCARDED_RESPONSES`], while waiting for the response.
     ///
     /// Returns the parsed response, or an error in the case that an
-    /// I/O issue occurred or an unexpected response was received.
-    #[tracing::instrument(skip(self, command))]
-    pub async fn send_command(&self, command: Command) -> Result<Response, CommandError> {
+    /// I/O issue occurred, too many unexpected responses were received or the
+    /// timeout elapsed.
+    #[tracing::instrument(parent = &self.span, skip(self, command))]
+    pub async fn send_command_timeout(
+        &self,
+        command: Command,
+        timeout: Duration,
+    ) -> Result<Response, CommandError> {
         // Get command type.
         let command_type = command.command_type();
         // Create a buffer for the response.
-        let mut buf = vec![0u8; 1024];
+        let mut buf = vec![0u8; self.max_response_size];
         // Send the command.
         let cmd_bytes = command.to_bytes();
         tracing::debug!("Sending command {:?} to {}", command_type, self.target_addr);
         self.socket.send_to(&cmd_bytes, self.target_addr).await?;
-        let (len, _src) = self.socket.recv_from(&mut buf).await?;
-        let data = &buf[..len];
+        self.metrics.command_sent(cmd_bytes.len());
+
+        let deadline = Instant::now() + timeout;
+        let mut discarded = 0;
+        loop {
+            let recv = self.socket.recv_from(&mut buf);
+            let Ok(res) = tokio::time::timeout_at(deadline, recv).await else {
+                self.metrics.timeout();
+                return Err(CommandError::Timeout(timeout));
+            };
+            let (len, src) = res?;
+            self.metrics.response_received();
+
+            // Ignore other devices on the same network.
+            if src.ip() != self.target_addr.ip() {
+                if discarded == MAX_DISCARDED_RESPONSES {
+                    return Err(CommandError::UnexpectedSource(src));
+                }
+                tracing::debug!("Discarding datagram from {src} while awaiting {command_type:?}");
+                discarded += 1;
+                continue;
+            }
+            warn_if_truncated(len, &buf);
 
-        // Verify the response is for the command we sent.
-        if len > 0 && data[0] == command_type as u8 {
-            // Parse the response.
-            match Response::try_from(data) {
+            // Verify the response echoes the command we sent. The echo byte is
+            // compared directly, as short replies to data packets parse as
+            // `BufferFree` despite echoing `SampleData`.
+            let bytes = &buf[..len];
+            let parsed = match Response::try_from(bytes) {
+                Ok(response) if bytes[0] == command_type.opcode() => return Ok(response),
                 Ok(response) => Ok(response),
-                Err(e) => Err(CommandError::Parse(e)),
+                // Malformed responses to other commands are discarded too,
+                // e.g. a truncated stray `GetFullInfo` reply.
+                Err(ResponseParseError::EmptyResponse) => Err(ResponseParseError::EmptyResponse),
+                Err(_) if bytes[0] != command_type.opcode() => Response::try_from_lenient(bytes),
+                Err(e) => Err(e),
+            };
+            let response = parsed.inspect_err(|e| {
+                tracing::debug!(
+                    "Malformed response from {src}: {e}: [{}]",
+                    hex_preview(bytes)
+                );
+            })?;
+            if discarded == MAX_DISCARDED_RESPONSES {
+                return Err(CommandError::UnexpectedResponse {
+                    expected: command_type,
+                    got: response,
+                });
+            }
+            let echo = bytes[0];
+            tracing::debug!("Discarding response {echo:#x} while awaiting {command_type:?}");
+            discarded += 1;
+        }
+    }
+
+    /// Discard all datagrams queued on the client's sockets without waiting.
+    ///
+    /// Stale responses, e.g. buffer size responses left over from a burst of
+    /// data, may otherwise be read by the next command. Call this before a
+    /// command exchange to start from an empty queue.
+    ///
+    /// Only datagrams the runtime has already observed as readable are
+    /// drained, so datagrams arriving at the same moment may be missed.
+    ///
+    /// Returns the number of datagrams discarded.
+    pub fn drain_pending(&self) -> usize {
+        let mut buf = vec![0u8; self.max_response_size];
+        let mut drained = 0;
+        let mut sockets = vec![&self.socket];
+        if !Arc::ptr_eq(&self.socket, &self.data_socket) {
+            sockets.push(&self.data_socket);
+        }
+        for socket in sockets {
+            while let Ok((len, src)) = socket.try_recv_from(&mut buf) {
+                tracing::trace!("Draining stale {len} byte datagram from {src}");
+                drained += 1;
+            }
+        }
+        drained
+    }
+
+    /// Send a command, re-sending it up to `retries` times if an attempt fails.
+    ///
+    /// As communication is over UDP, either the command or its response may be
+    /// silently dropped. Each attempt waits up to `per_attempt_timeout` for a
+    /// response (see [`Client::send_command_timeout`]) before trying again.
+    ///
+    /// Returns the first successfully parsed response, or the last error if
+    /// all `retries + 1` attempts fail.
+    #[tracing::instrument(parent = &self.span, skip(self, command))]
+    pub async fn send_command_with_retries(
+        &self,
+        command: Command,
+        retries: usize,
+        per_attempt_timeout: Duration,
+    ) -> Result<Response, CommandError> {
+        let mut attempt = 0;
+        loop {
+            let result = self
+                .send_command_timeout(command.clone(), per_attempt_timeout)
+                .await;
+            match result {
+                Err(e) if attempt < retries => {
+                    attempt += 1;
+                    self.metrics.retry();
+                    tracing::debug!("Command failed ({e}), retrying ({attempt}/{retries})");
+                }
+                result => return result,
             }
-        } else if len > 0 {
-            // We received a response, but it's for a different command.
-            Err(CommandError::UnexpectedResponse {
-                expected: command_type,
-                actual: data[0],
-            })
-        } else {
-            // Received an empty response
-            Err(CommandError::Parse(ResponseParseError::EmptyResponse))
         }
     }
 
+    /// Check whether the device is reachable by pinging its ALIVE port.
+    ///
+    /// Sends [`ALIVE_PING`] from a fresh socket, so as not to interfere with
+    /// in-flight commands, and waits up to `timeout` for any reply. This is
+    /// much cheaper than [`Client::get_full_info`] for regular health checks.
+    ///
+    /// Returns `false` if no reply arrived within the timeout, or if the
+    /// target host reported the port as unreachable.
+    pub async fn is_alive(&self, timeout: Duration) -> Result<bool, CommandError> {
+        Ok(ping_alive(self.bind_ip, self.alive_addr, timeout).await?)
+    }
+
+    /// Start a background task checking whether the device is reachable.
+    ///
+    /// Every `interval`, [`Command::GetRingbufferEmptySampleCount`] is sent
+    /// to the CMD port from a fresh socket, so as not to interfere with
+    /// in-flight commands, waiting up to `interval` for a reply. The result of
+    /// the most recent check is published via [`KeepAlive::status`], starting
+    /// as `false` until the first reply arrives.
+    ///
+    /// The task runs until the returned [`KeepAlive`] is dropped.
+    pub fn start_keepalive(&self, interval: Duration) -> KeepAlive {
+        let bind_ip = self.bind_ip;
+        let target_addr = self.target_addr;
+        let (tx, rx) = watch::channel(false);
+        let task = async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let alive = match ping_buffer_free(bind_ip, target_addr, interval).await {
+                    Ok(alive) => alive,
+                    Err(e) => {
+                        tracing::debug!("Keep-alive ping failed: {e}");
+                        false
+                    }
+                };
+                tx.send_if_modified(|status| std::mem::replace(status, alive) != alive);
+            }
+        };
+        let task = tokio::spawn(task.instrument(self.span.clone()));
+        KeepAlive { status: rx, task }
+    }
+
     /// Get the amount of free space in the device's buffer.
     ///
     /// Returns the number of free points in the buffer, or an error.
@@ -124,16 +520,146 @@ impl Client {
             .await?;
         match response {
             Response::BufferFree(free) => Ok(free),
-            _ => unreachable!(),
+            res => Err(unexpected_response(
+                CommandType::GetRingbufferEmptySampleCount,
+                res,
+            )),
+        }
+    }
+
+    /// Query the device for its full info.
+    ///
+    /// Useful for polling state like battery percentage and temperature
+    /// without re-running discovery.
+    pub async fn get_full_info(&self) -> Result<LaserInfo, CommandError> {
+        let response = self.send_command(Command::GetFullInfo).await?;
+        match response {
+            Response::FullInfo(info) => {
+                self.record_serial(info.header.serial_number);
+                self.cache_output(Some(info.header.status.output_enabled()));
+                Ok(info)
+            }
+            res => Err(unexpected_response(CommandType::GetFullInfo, res)),
         }
     }
 
     /// Enable or disable laser output.
+    ///
+    /// If the device echoes its output state in the acknowledgment, it is
+    /// verified against the requested state. A `StateMismatch` error is
+    /// returned if they differ (e.g. output remains off due to interlock).
+    ///
+    /// The resulting state is cached, see [`Client::cached_output`].
     pub async fn set_output(&self, enable: bool) -> Result<(), CommandError> {
-        let response = self.send_command(Command::SetOutput(enable)).await?;
+        let result = match self.send_command(Command::SetOutput(enable)).await {
+            Ok(Response::Ack {
+                value: Some(echo), ..
+            }) if (echo != 0) != enable => Err(CommandError::StateMismatch {
+                requested: enable,
+                reported: echo != 0,
+            }),
+            Ok(Response::Ack { .. }) => Ok(()),
+            Ok(res) => Err(unexpected_response(CommandType::SetOutput, res)),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(()) => self.cache_output(Some(enable)),
+            Err(CommandError::StateMismatch { reported, .. }) => self.cache_output(Some(reported)),
+            // The command may or may not have been applied.
+            Err(_) => self.cache_output(None),
+        }
+        result
+    }
+
+    /// Enable or disable laser output, skipping the command if the cached
+    /// output state already matches.
+    ///
+    /// Useful for UI toggles that may repeatedly request the same state. The
+    /// cache only reflects this client's commands and responses, so it may be
+    /// stale if output was changed by another controller or by the device,
+    /// e.g. on interlock. Use [`Client::set_output`] to always send.
+    pub async fn set_output_cached(&self, enable: bool) -> Result<(), CommandError> {
+        if self.cached_output() == Some(enable) {
+            tracing::trace!(
+                "Output already {}, skipping",
+                if enable { "on" } else { "off" }
+            );
+            return Ok(());
+        }
+        self.set_output(enable).await
+    }
+
+    /// The last known output state, or `None` if unknown.
+    ///
+    /// Updated by [`Client::set_output`] and [`Client::get_full_info`]. The
+    /// state becomes unknown when a `SetOutput` command fails for reasons
+    /// other than a reported state mismatch.
+    pub fn cached_output(&self) -> Option<bool> {
+        match self.output_state.load(Ordering::Relaxed) {
+            OUTPUT_DISABLED => Some(false),
+            OUTPUT_ENABLED => Some(true),
+            _ => None,
+        }
+    }
+
+    /// Update the cached output state.
+    fn cache_output(&self, enabled: Option<bool>) {
+        let state = match enabled {
+            None => OUTPUT_UNKNOWN,
+            Some(false) => OUTPUT_DISABLED,
+            Some(true) => OUTPUT_ENABLED,
+        };
+        self.output_state.store(state, Ordering::Relaxed);
+    }
+
+    /// Enable or disable laser output, confirming the new state by re-reading
+    /// the device's status.
+    ///
+    /// After [`Client::set_output`] succeeds, the full device info is fetched
+    /// and a `StateMismatch` error is returned if its output enabled flag
+    /// disagrees with the requested state. This catches devices that
+    /// acknowledge the command but leave output off, e.g. due to interlock.
+    pub async fn set_output_verified(&self, enable: bool) -> Result<(), CommandError> {
+        self.set_output(enable).await?;
+        let reported = self.get_full_info().await?.header.status.output_enabled();
+        if reported != enable {
+            return Err(CommandError::StateMismatch {
+                requested: enable,
+                reported,
+            });
+        }
+        Ok(())
+    }
+
+    /// Safely stop the show by parking the galvos at the center with the beam
+    /// off, then disabling output.
+    ///
+    /// Sends [`IDLE_MESSAGES`] messages of [`SampleData::idle`] points so that
+    /// stale content queued on the device is followed by blank points, then
+    /// calls [`Client::set_output`] with `false`.
+    pub async fn blank_output(&self) -> Result<(), CommandError> {
+        for message_num in 0..IDLE_MESSAGES as u8 {
+            let idle = SampleData::idle(message_num, 0, MAX_POINTS_PER_MESSAGE);
+            self.send_sample_data(idle).await?;
+        }
+        self.set_output(false).await
+    }
+
+    /// Get the device's safety-relevant state, e.g. before enabling output.
+    ///
+    /// Fetches the full device info and resolves the status flags for the
+    /// device's firmware version.
+    pub async fn safety_state(&self) -> Result<SafetyState, CommandError> {
+        let info = self.get_full_info().await?;
+        Ok(info.header.safety_state())
+    }
+
+    /// Set the DAC scan rate in points per second.
+    pub async fn set_dac_rate(&self, rate: u32) -> Result<(), CommandError> {
+        let response = self.send_command(Command::SetDacRate(rate)).await?;
         match response {
-            Response::Ack => Ok(()),
-            _ => unreachable!(),
+            Response::Ack { .. } => Ok(()),
+            res => Err(unexpected_response(CommandType::SetDacRate, res)),
         }
     }
 
@@ -143,8 +669,1511 @@ impl Client {
             .send_command(Command::EnableBufferSizeResponseOnData(enable))
             .await?;
         match response {
-            Response::Ack => Ok(()),
-            _ => unreachable!(),
+            Response::Ack { .. } => Ok(()),
+            res => Err(unexpected_response(
+                CommandType::EnableBufferSizeResponseOnData,
+                res,
+            )),
+        }
+    }
+}
+
+impl Client {
+    /// Send point data to the device's DATA port.
+    ///
+    /// Does not wait for a response. If buffer size responses are enabled, use
+    /// [`Client::recv_buffer_free`] to receive the device's reply.
+    ///
+    /// Returns [`CommandError::Invalid`] without sending anything if the data
+    /// fails [`Command::validate`].
+    #[tracing::instrument(level = "debug", parent = &self.span, skip_all)]
+    pub async fn send_sample_data(&self, data: SampleData) -> Result<(), CommandError> {
+        let command = Command::SampleData(data);
+        command.validate()?;
+        let mut bytes = Vec::with_capacity(MAX_MESSAGE_SIZE);
+        command.write_bytes(&mut bytes);
+        self.data_socket.send_to(&bytes, self.data_addr).await?;
+        self.metrics.data_packet_sent(bytes.len());
+        Ok(())
+    }
+
+    /// Wait up to `timeout` for a buffer size response on the DATA port.
+    ///
+    /// Returns the reported free buffer space, or `None` if no response was
+    /// received within the timeout.
+    #[tracing::instrument(level = "debug", parent = &self.span, skip(self))]
+    pub async fn recv_buffer_free(&self, timeout: Duration) -> Result<Option<u16>, CommandError> {
+        let mut buf = vec![0u8; self.max_response_size];
+        let recv = self.recv_buffer_free_into(&mut buf);
+        match tokio::time::timeout(timeout, recv).await {
+            Ok(res) => res.map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Stream the buffer size responses received on the DATA port.
+    ///
+    /// Yields the reported free buffer space of each response, in the order
+    /// received. Enable buffer size responses with
+    /// [`Client::enable_buffer_size_response`] for the device to send them.
+    ///
+    /// The stream never ends. Errors, e.g. for malformed responses, are
+    /// yielded without ending the stream.
+    pub fn stream_buffer_updates(&self) -> impl Stream<Item = Result<u16, CommandError>> + '_ {
+        let buf = vec![0u8; self.max_response_size];
+        futures::stream::unfold(buf, move |mut buf| async move {
+            let res = self.recv_buffer_free_into(&mut buf).await;
+            Some((res, buf))
+        })
+    }
+
+    /// Wait for a buffer size response on the DATA port, receiving into `buf`.
+    async fn recv_buffer_free_into(&self, buf: &mut [u8]) -> Result<u16, CommandError> {
+        let (len, _src) = self.data_socket.recv_from(buf).await?;
+        warn_if_truncated(len, buf);
+        match Response::try_from(&buf[..len])? {
+            // Replies without the echoed message number parse as `BufferFree`.
+            Response::SampleDataAck {
+                buffer_free: free, ..
+            } => {
+                self.metrics.buffer_free_received();
+                Ok(free)
+            }
+            Response::BufferFree(free) if buf[0] == CommandType::SampleData.opcode() => {
+                self.metrics.buffer_free_received();
+                Ok(free)
+            }
+            res => Err(CommandError::UnexpectedResponse {
+                expected: CommandType::SampleData,
+                got: res,
+            }),
+        }
+    }
+
+    /// Stream points to the device's DATA port, pacing sends to its buffer.
+    ///
+    /// Points are chunked into messages of at most `MAX_POINTS_PER_MESSAGE`
+    /// with incrementing message numbers. As the stream has no frame
+    /// boundaries, all points are sent as part of the same frame.
+    ///
+    /// Only up to [`StreamConfig::max_buffered_points`] are kept queued on the
+    /// device. Between device responses, the free space is estimated from the
+    /// DAC rate. Enable buffer size responses with
+    /// [`Client::enable_buffer_size_response`] beforehand so that the estimate
+    /// is corrected as the device reports its actual free space. If the DAC
+    /// rate is `0`, the free space can't be estimated, so the device's reports
+    /// are trusted as they arrive.
+    ///
+    /// At least one point is always kept queued, even if
+    /// [`StreamConfig::max_buffered_points`] is `0`.
+    ///
+    /// If [`StreamConfig::repeat_last_on_underflow`] is set, the most recently
+    /// sent message is repeated whenever the stream stalls for long enough
+    /// that the device would otherwise run out of points.
+    ///
+    /// Returns once the stream ends and all of its points have been sent.
+    #[tracing::instrument(parent = &self.span, skip(self, points))]
+    pub async fn stream_points(
+        &self,
+        points: impl Stream<Item = Point>,
+        config: StreamConfig,
+    ) -> Result<(), CommandError> {
+        let mut points = std::pin::pin!(points);
+
+        // Only track the portion of the device buffer within our max latency.
+        // Without room for a single point, nothing would ever be sent.
+        let max_buffered = config.max_buffered_points().max(1);
+        let mut buffer = BufferState {
+            total_size: max_buffered,
+            free_space: max_buffered,
+            threshold: max_buffered.min(MAX_POINTS_PER_MESSAGE as u16),
+            last_update_time: 0,
+        };
+
+        // Times are offset by 1ms, as `BufferState` treats 0 as "never updated".
+        let start = Instant::now();
+        let now_ms = || start.elapsed().as_millis() as u64 + 1;
+
+        let mut counter = MessageCounter::default();
+        // The most recently sent points, kept only if they may be repeated.
+        let mut last_points: Option<Vec<Point>> = None;
+        // Whether the point stream has ended, after which it must not be polled.
+        let mut ended = false;
+
+        loop {
+            let now = now_ms();
+            let free = buffer.estimate_current_free_space(now, config.dac_rate);
+
+            if free >= buffer.threshold {
+                // Collect as many points as fit within the estimated free space.
+                let max_points = (free as usize).min(MAX_POINTS_PER_MESSAGE);
+                let mut batch = Vec::with_capacity(max_points);
+                let mut stalled = false;
+                while batch.len() < max_points {
+                    // When repeating, only wait for as long as the queued points
+                    // last. Until a message has been sent, there's nothing to
+                    // repeat, so wait indefinitely for the first point.
+                    let next = if config.repeat_last_on_underflow
+                        && (last_points.is_some() || !batch.is_empty())
+                    {
+                        let queued = (buffer.total_size - free) as u64;
+                        let drain = match config.dac_rate {
+                            0 => Duration::from_millis(10),
+                            rate => Duration::from_micros(queued * 1_000_000 / rate as u64),
+                        };
+                        match tokio::time::timeout(drain, points.next()).await {
+                            Ok(next) => next,
+                            Err(_) => {
+                                stalled = true;
+                                break;
+                            }
+                        }
+                    } else {
+                        points.next().await
+                    };
+                    match next {
+                        Some(point) => batch.push(point),
+                        None => {
+                            ended = true;
+                            break;
+                        }
+                    }
+                }
+                if batch.is_empty() {
+                    match (&last_points, stalled) {
+                        (Some(last), true) => {
+                            tracing::trace!("Point stream stalled, repeating last message");
+                            batch = last.clone();
+                        }
+                        _ => return Ok(()),
+                    }
+                }
+                if config.repeat_last_on_underflow {
+                    last_points = Some(batch.clone());
+                }
+                let n_points = batch.len() as u16;
+                let data = counter.next(batch);
+                tracing::trace!("Sending {n_points} points, estimated free {free}");
+                self.send_sample_data(data).await?;
+                if ended {
+                    return Ok(());
+                }
+                let now = now_ms();
+                let free = buffer.estimate_current_free_space(now, config.dac_rate);
+                buffer.update_free_space(free, now);
+                buffer.consume(n_points);
+                continue;
+            }
+
+            // Wait for a buffer update, or until the buffer should have drained
+            // enough to send again.
+            let deficit = (buffer.threshold - free) as u64;
+            let wait_ms = match config.dac_rate {
+                0 => 10,
+                rate => (deficit * 1_000).div_ceil(rate as u64).max(1),
+            };
+            let wait = Duration::from_millis(wait_ms);
+            match self.recv_buffer_free(wait).await {
+                Ok(Some(device_free)) => {
+                    let now = now_ms();
+                    let free = BufferState::latency_capped_free(
+                        config.rx_buffer_size,
+                        device_free,
+                        config.dac_rate,
+                        config.max_latency_ms,
+                    );
+                    // Responses may predate our most recent sends, so only
+                    // ever correct the estimate downwards. Without a DAC rate
+                    // the estimate never grows, so the report is all we have.
+                    let free = match config.dac_rate {
+                        0 => free.min(buffer.total_size),
+                        rate => free.min(buffer.estimate_current_free_space(now, rate)),
+                    };
+                    buffer.update_free_space(free, now);
+                }
+                Ok(None) => (),
+                Err(CommandError::Io(e)) => return Err(e.into()),
+                Err(e) => tracing::warn!("Failed to receive buffer free: {e}"),
+            }
+        }
+    }
+
+    /// Stream points to the device's DATA port at a fixed rate.
+    ///
+    /// Unlike [`Client::stream_points`], this is open-loop: messages of up to
+    /// `MAX_POINTS_PER_MESSAGE` points are sent on a fixed schedule derived
+    /// from `points_per_second`, without enabling or reading buffer size
+    /// responses. This avoids the response overhead, but any drift between
+    /// the schedule and the device's DAC rate, or any dropped packets, go
+    /// uncorrected and gradually under- or overflow the device's buffer. It is
+    /// best suited to short streams over reliable wired links.
+    ///
+    /// If the stream stalls, the schedule is delayed rather than catching up
+    /// with a burst of messages.
+    ///
+    /// Returns once the stream ends and all of its points have been sent.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `points_per_second` is `0`.
+    #[tracing::instrument(parent = &self.span, skip(self, points))]
+    pub async fn stream_points_paced(
+        &self,
+        points: impl Stream<Item = Point>,
+        points_per_second: u32,
+    ) -> Result<(), CommandError> {
+        assert!(
+            points_per_second > 0,
+            "`points_per_second` must be non-zero"
+        );
+        let chunk_nanos = MAX_POINTS_PER_MESSAGE as u64 * 1_000_000_000;
+        let period = Duration::from_nanos(chunk_nanos / points_per_second as u64);
+        let mut interval = tokio::time::interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let mut chunks = std::pin::pin!(points.chunks(MAX_POINTS_PER_MESSAGE));
+        let mut counter = MessageCounter::default();
+        while let Some(points) = chunks.next().await {
+            interval.tick().await;
+            self.send_sample_data(counter.next(points)).await?;
+        }
+        Ok(())
+    }
+}
+
+/// A device within a [`Group`].
+#[derive(Debug)]
+pub struct GroupMember {
+    /// The IP address the device reported in its info.
+    pub ip: Ipv4Addr,
+    /// The client connected to the device.
+    pub client: Client,
+    /// The most recently known state of the device's buffer.
+    pub buffer: BufferState,
+}
+
+/// A group of clients for controlling multiple devices at once.
+///
+/// Operations on the group fan out to all devices concurrently. Errors are
+/// reported per device rather than aborting the whole group.
+#[derive(Debug)]
+pub struct Group {
+    members: Vec<GroupMember>,
+    /// The reference for buffer update times.
+    start: Instant,
+}
+
+impl Group {
+    /// Create a client for each of the given devices.
+    ///
+    /// Each device's buffer state is initialized from its info.
+    pub async fn from_infos(bind_ip: IpAddr, infos: &[LaserInfo]) -> Result<Self, CommandError> {
+        let start = Instant::now();
+        let mut members = Vec::with_capacity(infos.len());
+        for info in infos {
+            let ip = info.header.ip_addr;
+            let client = Client::new(bind_ip, ip).await?;
+            client.record_serial(info.header.serial_number);
+            members.push(GroupMember {
+                ip,
+                client,
+                // Times are offset by 1ms, as `BufferState` treats 0 as "never updated".
+                buffer: BufferState::from_laser_info(info, 1),
+            });
+        }
+        Ok(Group { members, start })
+    }
+
+    /// The devices in the group, in the order they were given.
+    pub fn members(&self) -> &[GroupMember] {
+        &self.members
+    }
+
+    /// Enable or disable the output of all devices.
+    pub async fn set_output_all(&self, enable: bool) -> Vec<(Ipv4Addr, Result<(), CommandError>)> {
+        let results = join_all(self.members.iter().map(|m| m.client.set_output(enable))).await;
+        self.members.iter().map(|m| m.ip).zip(results).collect()
+    }
+
+    /// Query the free buffer space of all devices, updating their tracked
+    /// buffer states.
+    ///
+    /// Devices that fail to respond keep their previous buffer state.
+    pub async fn update_buffers(&mut self) -> Vec<(Ipv4Addr, Result<u16, CommandError>)> {
+        let results = join_all(self.members.iter().map(|m| m.client.get_buffer_free())).await;
+        let now = self.start.elapsed().as_millis() as u64 + 1;
+        let mut reports = Vec::with_capacity(results.len());
+        for (member, result) in self.members.iter_mut().zip(results) {
+            if let Ok(free) = result {
+                member.buffer.update_free_space(free, now);
+            }
+            reports.push((member.ip, result));
         }
+        reports
+    }
+}
+
+/// A handle to the background task started by [`Client::start_keepalive`].
+///
+/// Dropping the handle stops the task.
+#[derive(Debug)]
+pub struct KeepAlive {
+    status: watch::Receiver<bool>,
+    task: JoinHandle<()>,
+}
+
+impl KeepAlive {
+    /// A receiver for whether the most recent ping was answered.
+    ///
+    /// Changes are only published when the status flips, so
+    /// [`watch::Receiver::changed`] can be used to react to the device going
+    /// offline or coming back online.
+    pub fn status(&self) -> watch::Receiver<bool> {
+        self.status.clone()
+    }
+
+    /// Whether the most recent ping was answered.
+    pub fn is_connected(&self) -> bool {
+        *self.status.borrow()
+    }
+}
+
+impl Drop for KeepAlive {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        if !self.auto_disable {
+            return;
+        }
+        // `Drop` can't be async, so fire-and-forget the raw command.
+        let bytes = Command::SetOutput(false).to_bytes();
+        match self.socket.try_send_to(&bytes, self.target_addr) {
+            Ok(_) => tracing::debug!("Sent output disable to {}", self.target_addr),
+            Err(e) => tracing::warn!("Failed to disable output on drop: {e}"),
+        }
+    }
+}
+
+/// Create the span identifying a client's target device.
+fn device_span(ip: IpAddr) -> tracing::Span {
+    tracing::info_span!("lasercube", %ip, serial = tracing::field::Empty)
+}
+
+/// Log a warning if a received datagram filled the whole buffer, as it may
+/// have been truncated.
+pub(crate) fn warn_if_truncated(len: usize, buf: &[u8]) {
+    if len == buf.len() {
+        tracing::warn!("Received datagram filled the {len} byte buffer and may be truncated");
+    }
+}
+
+/// Send [`ALIVE_PING`] to `alive_addr` from a fresh socket bound to `bind_ip`
+/// and wait up to `timeout` for any reply.
+async fn ping_alive(
+    bind_ip: IpAddr,
+    alive_addr: SocketAddr,
+    timeout: Duration,
+) -> std::io::Result<bool> {
+    let socket = UdpSocket::bind(SocketAddr::new(bind_ip, 0)).await?;
+    socket.connect(alive_addr).await?;
+    tracing::trace!("Sending alive ping to {alive_addr}");
+    socket.send(&ALIVE_PING).await?;
+    let mut buf = [0u8; 64];
+    match tokio::time::timeout(timeout, socket.recv(&mut buf)).await {
+        Ok(Ok(_)) => Ok(true),
+        Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => Ok(false),
+        Ok(Err(e)) => Err(e),
+        Err(_) => Ok(false),
+    }
+}
+
+/// Send [`Command::GetRingbufferEmptySampleCount`] to `target_addr` from a
+/// fresh socket bound to `bind_ip` and wait up to `timeout` for its reply.
+async fn ping_buffer_free(
+    bind_ip: IpAddr,
+    target_addr: SocketAddr,
+    timeout: Duration,
+) -> std::io::Result<bool> {
+    let command = Command::GetRingbufferEmptySampleCount;
+    let socket = UdpSocket::bind(SocketAddr::new(bind_ip, 0)).await?;
+    socket.connect(target_addr).await?;
+    tracing::trace!("Sending keep-alive buffer query to {target_addr}");
+    socket.send(&command.to_bytes()).await?;
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut buf = [0u8; 64];
+    loop {
+        match tokio::time::timeout_at(deadline, socket.recv(&mut buf)).await {
+            Ok(Ok(len)) if buf[..len].first() == Some(&command.command_type().opcode()) => {
+                return Ok(true);
+            }
+            // Ignore anything that isn't a reply to the query.
+            Ok(Ok(_)) => continue,
+            Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => return Ok(false),
+            Ok(Err(e)) => return Err(e),
+            Err(_) => return Ok(false),
+        }
+    }
+}
+
+/// Produce the error for a response whose echo byte matched the command sent,
+/// but whose parsed variant did not.
+fn unexpected_response(expected: CommandType, response: Response) -> CommandError {
+    tracing::warn!("Unexpected response to {expected:?}: {response:?}");
+    CommandError::UnexpectedResponse {
+        expected,
+        got: response,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lasercube_core::{port, ConnectionType, LaserInfoHeader, PointError, StatusFlags};
+    use std::net::Ipv6Addr;
+
+    /// Bind a local socket to act as a fake device, returning it along with a
+    /// client targeting it.
+    async fn mock_device() -> (UdpSocket, Client) {
+        let localhost = Ipv4Addr::LOCALHOST;
+        let device = UdpSocket::bind((localhost, 0)).await.unwrap();
+        let device_port = device.local_addr().unwrap().port();
+        let ports = Ports {
+            alive: device_port,
+            cmd: device_port,
+            data: device_port,
+        };
+        let client = Client::new_with_ports(localhost.into(), localhost.into(), ports)
+            .await
+            .unwrap();
+        (device, client)
+    }
+
+    /// Log output captured by [`capture_logs`].
+    #[derive(Clone, Default)]
+    struct Logs(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Logs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Logs {
+        fn contents(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    /// Capture logs up to the given level on the current thread until the
+    /// returned guard is dropped.
+    fn capture_logs(level: tracing::Level) -> (Logs, tracing::subscriber::DefaultGuard) {
+        let logs = Logs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_max_level(level)
+            .with_ansi(false)
+            .finish();
+        (logs, tracing::subscriber::set_default(subscriber))
+    }
+
+    #[tokio::test]
+    async fn test_new_with_ports() {
+        let localhost = Ipv4Addr::LOCALHOST;
+        let device = UdpSocket::bind((localhost, 0)).await.unwrap();
+        let ports = Ports {
+            cmd: device.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+        let client = Client::new_with_ports(localhost.into(), localhost.into(), ports)
+            .await
+            .unwrap();
+        assert_eq!(client.target_addr, SocketAddr::from((localhost, ports.cmd)));
+        assert_eq!(client.data_addr, SocketAddr::from((localhost, port::DATA)));
+        assert_eq!(
+            client.alive_addr,
+            SocketAddr::from((localhost, port::ALIVE))
+        );
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (len, src) = device.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], &[0x8a]);
+            device
+                .send_to(&[0x8a, 0x00, 0xe8, 0x03], src)
+                .await
+                .unwrap();
+        });
+        assert_eq!(client.get_buffer_free().await.unwrap(), 1000);
+        responder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_with_socket() {
+        let localhost = Ipv4Addr::LOCALHOST;
+        let device = UdpSocket::bind((localhost, 0)).await.unwrap();
+        let socket = UdpSocket::bind((localhost, 0)).await.unwrap();
+        let local_addr = socket.local_addr().unwrap();
+        let mut client = Client::with_socket(socket, localhost);
+        assert_eq!(client.target_addr, SocketAddr::from((localhost, port::CMD)));
+        assert_eq!(client.data_addr, SocketAddr::from((localhost, port::DATA)));
+        client
+            .target_addr
+            .set_port(device.local_addr().unwrap().port());
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (len, src) = device.recv_from(&mut buf).await.unwrap();
+            // Commands are sent from the provided socket.
+            assert_eq!(src, local_addr);
+            assert_eq!(&buf[..len], &[0x80, 0x01]);
+            device.send_to(&[0x80, 0x01], src).await.unwrap();
+        });
+        client.set_output(true).await.unwrap();
+        responder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_auto_disable_on_drop() {
+        let (device, mut client) = mock_device().await;
+        client.arm_auto_disable();
+        let device = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (len, src) = device.recv_from(&mut buf).await.unwrap();
+            device.send_to(&buf[..len], src).await.unwrap();
+            device
+        });
+        client.set_output(true).await.unwrap();
+        let device = device.await.unwrap();
+        drop(client);
+        let mut buf = [0u8; 64];
+        let recv = device.recv_from(&mut buf);
+        let (len, _) = tokio::time::timeout(Duration::from_secs(1), recv)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&buf[..len], &[CommandType::SetOutput as u8, 0]);
+
+        // Nothing is sent unless armed.
+        let (device, client) = mock_device().await;
+        drop(client);
+        let recv = device.recv_from(&mut buf);
+        assert!(tokio::time::timeout(Duration::from_millis(50), recv)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_truncated_response_warning() {
+        let (logs, _guard) = capture_logs(tracing::Level::WARN);
+
+        let (device, mut client) = mock_device().await;
+        assert_eq!(client.max_response_size(), MAX_RESPONSE_SIZE);
+        client.set_max_response_size(4);
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device
+                .send_to(&[0x8a, 0x00, 0xe8, 0x03, 0xFF, 0xFF], src)
+                .await
+                .unwrap();
+        });
+        // The tail of the response is lost, but the prefix still parses.
+        assert_eq!(client.get_buffer_free().await.unwrap(), 1000);
+        responder.await.unwrap();
+        let logs = logs.contents();
+        assert!(logs.contains("may be truncated"), "{logs}");
+    }
+
+    #[tokio::test]
+    async fn test_is_alive() {
+        let (device, client) = mock_device().await;
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (len, src) = device.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], &ALIVE_PING);
+            device.send_to(&buf[..len], src).await.unwrap();
+            // Close the socket so the next ping goes unanswered.
+        });
+        let timeout = Duration::from_millis(200);
+        assert!(client.is_alive(timeout).await.unwrap());
+        responder.await.unwrap();
+        assert!(!client.is_alive(timeout).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_keepalive() {
+        let (device, client) = mock_device().await;
+        let interval = Duration::from_millis(20);
+        let keepalive = client.start_keepalive(interval);
+        let mut status = keepalive.status();
+        assert!(!*status.borrow());
+
+        // Answer a few buffer queries, then stop answering.
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            for _ in 0..3 {
+                let (len, src) = device.recv_from(&mut buf).await.unwrap();
+                let expected = Command::GetRingbufferEmptySampleCount.to_bytes();
+                assert_eq!(&buf[..len], &expected[..]);
+                device
+                    .send_to(&[0x8a, 0x00, 0x00, 0x10], src)
+                    .await
+                    .unwrap();
+            }
+            device
+        });
+        let timeout = Duration::from_secs(1);
+        tokio::time::timeout(timeout, status.wait_for(|&alive| alive))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(keepalive.is_connected());
+
+        // Keep the socket open so that queries time out rather than being refused.
+        let _device = responder.await.unwrap();
+        tokio::time::timeout(timeout, status.wait_for(|&alive| !alive))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!keepalive.is_connected());
+
+        // Dropping the handle stops the task, closing the channel.
+        drop(keepalive);
+        let closed = tokio::time::timeout(timeout, status.changed()).await;
+        assert!(matches!(closed, Ok(Err(_))), "{closed:?}");
+    }
+
+    #[tokio::test]
+    async fn test_ipv6_target() {
+        let localhost = Ipv6Addr::LOCALHOST;
+        let Ok(device) = UdpSocket::bind((localhost, 0)).await else {
+            // IPv6 is unavailable on this host.
+            return;
+        };
+        let mut client = Client::with_target_ip(localhost.into(), localhost.into())
+            .await
+            .unwrap();
+        assert_eq!(
+            client.target_addr,
+            SocketAddr::new(localhost.into(), port::CMD)
+        );
+        assert_eq!(
+            client.data_addr,
+            SocketAddr::new(localhost.into(), port::DATA)
+        );
+        client
+            .target_addr
+            .set_port(device.local_addr().unwrap().port());
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device
+                .send_to(&[0x8a, 0x00, 0x10, 0x00], src)
+                .await
+                .unwrap();
+        });
+        assert_eq!(client.get_buffer_free().await.unwrap(), 0x10);
+        responder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_discard_unrelated_responses() {
+        let info_bytes = test_info().to_bytes();
+        let (device, client) = mock_device().await;
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            // A stray full info response, e.g. to another controller.
+            device.send_to(&info_bytes, src).await.unwrap();
+            device
+                .send_to(&[0x8a, 0x00, 0xe8, 0x03], src)
+                .await
+                .unwrap();
+
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device.send_to(&[0x78], src).await.unwrap();
+            device.send_to(&[0x80, 0x01], src).await.unwrap();
+
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device.send_to(&[0xFF, 0x00], src).await.unwrap();
+            device.send_to(&[0x78], src).await.unwrap();
+
+            // Too many unrelated responses.
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            for _ in 0..=MAX_DISCARDED_RESPONSES {
+                device.send_to(&[0x80], src).await.unwrap();
+            }
+        });
+        assert_eq!(client.get_buffer_free().await.unwrap(), 1000);
+        client.set_output(true).await.unwrap();
+        client.enable_buffer_size_response(true).await.unwrap();
+        let result = client.get_buffer_free().await;
+        assert!(matches!(
+            result,
+            Err(CommandError::UnexpectedResponse {
+                expected: CommandType::GetRingbufferEmptySampleCount,
+                got: Response::Ack {
+                    command_type: CommandType::SetOutput,
+                    value: None,
+                },
+            })
+        ));
+        responder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_discard_truncated_full_info() {
+        let info_bytes = test_info().to_bytes();
+        let (device, client) = mock_device().await;
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            // A stray full info response, truncated in transit.
+            device.send_to(&info_bytes[..10], src).await.unwrap();
+            device
+                .send_to(&[0x8a, 0x00, 0xe8, 0x03], src)
+                .await
+                .unwrap();
+        });
+        assert_eq!(client.get_buffer_free().await.unwrap(), 1000);
+        responder.await.unwrap();
+    }
+
+    // Relies on the whole 127.0.0.0/8 range being routed to loopback so that
+    // the other device can bind an address distinct from the mock device's.
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_discard_other_sources() {
+        let (device, client) = mock_device().await;
+        let other = UdpSocket::bind((Ipv4Addr::new(127, 0, 0, 19), 0))
+            .await
+            .unwrap();
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            // The same response from another device is ignored.
+            other.send_to(&[0x8a, 0x00, 0x01, 0x00], src).await.unwrap();
+            device
+                .send_to(&[0x8a, 0x00, 0xe8, 0x03], src)
+                .await
+                .unwrap();
+
+            // Too many datagrams from another device.
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            for _ in 0..=MAX_DISCARDED_RESPONSES {
+                other.send_to(&[0x8a, 0x00, 0x01, 0x00], src).await.unwrap();
+            }
+            other.local_addr().unwrap()
+        });
+        assert_eq!(client.get_buffer_free().await.unwrap(), 1000);
+        let result = client.get_buffer_free().await;
+        let other_addr = responder.await.unwrap();
+        assert!(
+            matches!(result, Err(CommandError::UnexpectedSource(src)) if src == other_addr),
+            "{result:?}"
+        );
+    }
+
+    #[test]
+    fn test_error_source_chain() {
+        use std::error::Error;
+
+        let io_err = std::io::Error::new(std::io::ErrorKind::ConnectionRefused, "refused");
+        let err = CommandError::from(io_err);
+        let source = err.source().unwrap();
+        let io_err = source.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io_err.kind(), std::io::ErrorKind::ConnectionRefused);
+
+        let err = CommandError::from(Response::try_from(&[][..]).unwrap_err());
+        let source = err.source().unwrap();
+        assert!(matches!(
+            source.downcast_ref::<ResponseParseError>(),
+            Some(ResponseParseError::EmptyResponse)
+        ));
+
+        // Invalid points chain through the validation error to the point error.
+        let point = Point::new([0, 0x1000], [0, 0, 0]);
+        let command = Command::SampleData(SampleData {
+            message_num: 0,
+            frame_num: 0,
+            points: vec![point],
+        });
+        let err = CommandError::from(command.validate().unwrap_err());
+        let validation_err = err.source().unwrap();
+        assert!(validation_err.is::<CommandValidationError>());
+        let point_err = validation_err.source().unwrap();
+        assert_eq!(
+            point_err.downcast_ref::<PointError>(),
+            Some(&PointError::Y(0x1000))
+        );
+    }
+
+    fn test_info() -> LaserInfo {
+        LaserInfo {
+            header: LaserInfoHeader {
+                fw_major: 0,
+                fw_minor: 13,
+                status: StatusFlags::OUTPUT_ENABLED,
+                dac_rate: 30_000,
+                max_dac_rate: 40_000,
+                rx_buffer_free: 5000,
+                rx_buffer_size: 6000,
+                battery_percent: 64,
+                temperature: 35,
+                model_number: 1,
+                conn_type: ConnectionType::Wifi,
+                serial_number: [1, 2, 3, 4, 5, 6],
+                ip_addr: Ipv4Addr::LOCALHOST,
+            },
+            model_name: "LaserCube".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_full_info() {
+        let info = test_info();
+        let (device, client) = mock_device().await;
+        let reply = info.to_bytes();
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (len, src) = device.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], &[CommandType::GetFullInfo as u8]);
+            device.send_to(&reply, src).await.unwrap();
+        });
+        assert_eq!(client.get_full_info().await.unwrap(), info);
+        responder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_output_cached() {
+        let (device, client) = mock_device().await;
+        assert_eq!(client.cached_output(), None);
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let mut received = vec![];
+            // Echo each command until the client is done.
+            while let Ok(Ok((len, src))) =
+                tokio::time::timeout(Duration::from_millis(100), device.recv_from(&mut buf)).await
+            {
+                received.push(buf[..len].to_vec());
+                device.send_to(&buf[..len], src).await.unwrap();
+            }
+            received
+        });
+        client.set_output_cached(true).await.unwrap();
+        assert_eq!(client.cached_output(), Some(true));
+        // Already enabled, so nothing is sent.
+        client.set_output_cached(true).await.unwrap();
+        client.set_output_cached(false).await.unwrap();
+        assert_eq!(client.cached_output(), Some(false));
+        // Uncached calls always send.
+        client.set_output(false).await.unwrap();
+
+        let received = responder.await.unwrap();
+        assert_eq!(received, [vec![0x80, 1], vec![0x80, 0], vec![0x80, 0]]);
+        assert_eq!(client.metrics().commands_sent, 3);
+    }
+
+    #[tokio::test]
+    async fn test_set_output_verified() {
+        let (device, client) = mock_device().await;
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let mut info = test_info();
+            for enabled in [true, false] {
+                // Acknowledge without echoing the state.
+                let (_, src) = device.recv_from(&mut buf).await.unwrap();
+                device.send_to(&[0x80], src).await.unwrap();
+                let (len, src) = device.recv_from(&mut buf).await.unwrap();
+                assert_eq!(&buf[..len], &[0x77]);
+                info.header.status.set(StatusFlags::OUTPUT_ENABLED, enabled);
+                device.send_to(&info.to_bytes(), src).await.unwrap();
+            }
+        });
+        client.set_output_verified(true).await.unwrap();
+        // The device reports output remaining off, e.g. due to interlock.
+        let result = client.set_output_verified(true).await;
+        assert!(
+            matches!(
+                result,
+                Err(CommandError::StateMismatch {
+                    requested: true,
+                    reported: false,
+                })
+            ),
+            "{result:?}"
+        );
+        responder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_set_output_state_mismatch() {
+        let (device, client) = mock_device().await;
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            // Echo the requested state, then report output remaining off.
+            let (len, src) = device.recv_from(&mut buf).await.unwrap();
+            device.send_to(&buf[..len], src).await.unwrap();
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device.send_to(&[0x80, 0x00], src).await.unwrap();
+        });
+        client.set_output(true).await.unwrap();
+        let result = client.set_output(true).await;
+        assert!(matches!(
+            result,
+            Err(CommandError::StateMismatch {
+                requested: true,
+                reported: false,
+            })
+        ));
+        responder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_command_timeout() {
+        // The device is bound but never responds.
+        let (_device, client) = mock_device().await;
+        let timeout = Duration::from_millis(50);
+        let result = client
+            .send_command_timeout(Command::GetFullInfo, timeout)
+            .await;
+        assert!(matches!(result, Err(CommandError::Timeout(t)) if t == timeout));
+    }
+
+    #[tokio::test]
+    async fn test_send_command_with_retries() {
+        const DROPPED: usize = 2;
+        let (device, client) = mock_device().await;
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            // Drop the first `DROPPED` packets and respond to the next.
+            for _ in 0..DROPPED {
+                device.recv_from(&mut buf).await.unwrap();
+            }
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device
+                .send_to(&[0x8a, 0x00, 0xe8, 0x03], src)
+                .await
+                .unwrap();
+        });
+        let response = client
+            .send_command_with_retries(
+                Command::GetRingbufferEmptySampleCount,
+                DROPPED,
+                Duration::from_millis(50),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response, Response::BufferFree(1000));
+        responder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stream_points_buffer_paced() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        const TOTAL_POINTS: usize = 2_000;
+        let config = StreamConfig {
+            max_latency_ms: 50,
+            dac_rate: 10_000,
+            rx_buffer_size: 6_000,
+            repeat_last_on_underflow: false,
+        };
+        let max_buffered = config.max_buffered_points() as usize;
+        assert_eq!(max_buffered, 500);
+
+        // A fake device that renders points at the DAC rate and acks each data
+        // packet with its free buffer space.
+        let (device, client) = mock_device().await;
+        let max_queued = Arc::new(AtomicUsize::new(0));
+        let device_max_queued = max_queued.clone();
+        let device_task = tokio::spawn(async move {
+            let start = Instant::now();
+            let mut buf = vec![0u8; 2048];
+            let mut received = 0;
+            loop {
+                let (len, src) = device.recv_from(&mut buf).await.unwrap();
+                received += (len - 4) / Point::SIZE;
+                let rendered = start.elapsed().as_secs_f64() * config.dac_rate as f64;
+                let queued = received.saturating_sub(rendered as usize);
+                device_max_queued.fetch_max(queued, Ordering::Relaxed);
+                let free = config.rx_buffer_size - queued as u16;
+                let [lo, hi] = free.to_le_bytes();
+                device.send_to(&[0xa9, lo, hi], src).await.unwrap();
+            }
+        });
+
+        let points = futures::stream::iter(vec![Point::CENTER_BLANK; TOTAL_POINTS]);
+        let start = Instant::now();
+        client.stream_points(points, config).await.unwrap();
+        let elapsed = start.elapsed();
+        device_task.abort();
+
+        // Sending must be paced to the DAC rate rather than flooded.
+        let min_render = (TOTAL_POINTS - max_buffered) as f64 / config.dac_rate as f64;
+        assert!(elapsed.as_secs_f64() >= min_render * 0.8, "{elapsed:?}");
+        let max_queued = max_queued.load(Ordering::Relaxed);
+        assert!(
+            max_queued <= max_buffered + MAX_POINTS_PER_MESSAGE,
+            "{max_queued}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_points_zero_capacity() {
+        const TOTAL_POINTS: usize = 20;
+        // An unknown buffer size leaves no room within the latency.
+        let config = StreamConfig {
+            rx_buffer_size: 0,
+            ..StreamConfig::from_laser_info(&test_info())
+        };
+        assert_eq!(config.max_buffered_points(), 0);
+        let (device, client) = mock_device().await;
+        let device_task = tokio::spawn(async move {
+            let mut buf = vec![0u8; 2048];
+            let mut received = 0;
+            while received < TOTAL_POINTS {
+                let (len, _) = device.recv_from(&mut buf).await.unwrap();
+                received += (len - 4) / Point::SIZE;
+            }
+            received
+        });
+
+        let points = futures::stream::iter(vec![Point::CENTER_BLANK; TOTAL_POINTS]);
+        let timeout = Duration::from_secs(5);
+        tokio::time::timeout(timeout, client.stream_points(points, config))
+            .await
+            .unwrap()
+            .unwrap();
+        let received = tokio::time::timeout(timeout, device_task).await;
+        assert_eq!(received.unwrap().unwrap(), TOTAL_POINTS);
+    }
+
+    #[tokio::test]
+    async fn test_stream_points_zero_dac_rate() {
+        const TOTAL_POINTS: usize = 1_000;
+        let config = StreamConfig {
+            max_latency_ms: 50,
+            dac_rate: 0,
+            rx_buffer_size: 300,
+            repeat_last_on_underflow: false,
+        };
+        assert_eq!(config.max_buffered_points(), 300);
+
+        // A fake device that renders points instantly, always reporting an
+        // empty buffer.
+        let (device, client) = mock_device().await;
+        let device_task = tokio::spawn(async move {
+            let mut buf = vec![0u8; 2048];
+            loop {
+                let (_, src) = device.recv_from(&mut buf).await.unwrap();
+                let [lo, hi] = config.rx_buffer_size.to_le_bytes();
+                device.send_to(&[0xa9, lo, hi], src).await.unwrap();
+            }
+        });
+
+        // Without trusting the device's reports, the stream stalls once the
+        // buffer first fills.
+        let points = futures::stream::iter(vec![Point::CENTER_BLANK; TOTAL_POINTS]);
+        let timeout = Duration::from_secs(5);
+        let result = tokio::time::timeout(timeout, client.stream_points(points, config)).await;
+        device_task.abort();
+        result.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stream_points_partial_final_batch() {
+        // Not a multiple of `MAX_POINTS_PER_MESSAGE`.
+        const TOTAL_POINTS: u16 = 150;
+        let config = StreamConfig {
+            max_latency_ms: 1000,
+            dac_rate: 10_000,
+            rx_buffer_size: 6_000,
+            repeat_last_on_underflow: false,
+        };
+        let (device, client) = mock_device().await;
+        let device_task = tokio::spawn(async move {
+            let mut buf = vec![0u8; 2048];
+            let mut received = 0;
+            while received < TOTAL_POINTS as usize {
+                let (len, _) = device.recv_from(&mut buf).await.unwrap();
+                received += (len - 4) / Point::SIZE;
+            }
+            received
+        });
+
+        // `Unfold` panics if polled again after ending.
+        let points = futures::stream::unfold(0, |i| async move {
+            (i < TOTAL_POINTS).then(|| (Point::new([i, i], [0xFFF; 3]), i + 1))
+        });
+        let streaming = client.stream_points(points, config);
+        tokio::time::timeout(Duration::from_secs(1), streaming)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(device_task.await.unwrap(), TOTAL_POINTS as usize);
+    }
+
+    /// Stream a single message worth of points followed by a stall, returning
+    /// all messages received by the device within `duration`.
+    async fn stream_stalled(repeat_last_on_underflow: bool, duration: Duration) -> Vec<Vec<u8>> {
+        let config = StreamConfig {
+            max_latency_ms: 50,
+            dac_rate: 10_000,
+            rx_buffer_size: 6_000,
+            repeat_last_on_underflow,
+        };
+        let (device, client) = mock_device().await;
+        let device_task = tokio::spawn(async move {
+            let mut messages = vec![];
+            let mut buf = vec![0u8; 2048];
+            let recv = async {
+                loop {
+                    let (len, _) = device.recv_from(&mut buf).await.unwrap();
+                    messages.push(buf[..len].to_vec());
+                }
+            };
+            let _ = tokio::time::timeout(duration, recv).await;
+            messages
+        });
+        let n_points = MAX_POINTS_PER_MESSAGE as u16;
+        let points = (0..n_points).map(|i| Point::new([i, i], [0xFFF; 3]));
+        let points = futures::stream::iter(points).chain(futures::stream::pending());
+        let streaming = client.stream_points(points, config);
+        assert!(tokio::time::timeout(duration, streaming).await.is_err());
+        device_task.await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stream_points_repeat_last_on_underflow() {
+        let messages = stream_stalled(true, Duration::from_millis(100)).await;
+        // The points drain in 14ms, so the stalled stream is repeated.
+        assert!(messages.len() >= 2, "{}", messages.len());
+        for (i, message) in messages.iter().enumerate() {
+            assert_eq!(message[2], i as u8);
+            assert_eq!(message[4..], messages[0][4..]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_points_no_repeat_by_default() {
+        let messages = stream_stalled(false, Duration::from_millis(50)).await;
+        assert_eq!(messages.len(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stream_points_paced_open_loop() {
+        const POINTS_PER_SECOND: u32 = 14_000;
+        const N_MESSAGES: usize = 10;
+        let (device, client) = mock_device().await;
+        let device_task = tokio::spawn(async move {
+            let mut buf = vec![0u8; 2048];
+            let mut times = vec![];
+            for _ in 0..N_MESSAGES {
+                let (len, _) = device.recv_from(&mut buf).await.unwrap();
+                assert_eq!(len, 4 + MAX_POINTS_PER_MESSAGE * Point::SIZE);
+                times.push(Instant::now());
+            }
+            times
+        });
+
+        let n_points = N_MESSAGES * MAX_POINTS_PER_MESSAGE;
+        let points = futures::stream::iter(vec![Point::CENTER_BLANK; n_points]);
+        let start = Instant::now();
+        client
+            .stream_points_paced(points, POINTS_PER_SECOND)
+            .await
+            .unwrap();
+
+        // One message of 140 points every 10ms, the first sent immediately.
+        let times = device_task.await.unwrap();
+        let period = Duration::from_millis(10);
+        for (i, time) in times.into_iter().enumerate() {
+            let expected = period * i as u32;
+            let elapsed = time - start;
+            assert!(
+                elapsed >= expected && elapsed < expected + period / 2,
+                "{elapsed:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stream_buffer_updates() {
+        let (device, client) = mock_device().await;
+        let data_addr = client.data_socket.local_addr().unwrap();
+        for free in [1000u16, 900, 1200] {
+            let [lo, hi] = free.to_le_bytes();
+            device.send_to(&[0xa9, lo, hi], data_addr).await.unwrap();
+        }
+        // A malformed response doesn't end the stream.
+        device.send_to(&[0x78], data_addr).await.unwrap();
+        device
+            .send_to(&[0xa9, 0x00, 0x10], data_addr)
+            .await
+            .unwrap();
+
+        let updates = client.stream_buffer_updates().take(5).collect::<Vec<_>>();
+        let updates = tokio::time::timeout(Duration::from_secs(1), updates)
+            .await
+            .unwrap();
+        let free: Vec<_> = updates.iter().filter_map(|res| res.as_ref().ok()).collect();
+        assert_eq!(free, [&1000, &900, &1200, &0x1000]);
+        assert!(matches!(
+            updates[3],
+            Err(CommandError::UnexpectedResponse { .. })
+        ));
+        assert_eq!(client.metrics().buffer_free_responses, 4);
+    }
+
+    #[tokio::test]
+    async fn test_send_sample_data() {
+        let (device, client) = mock_device().await;
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (len, src) = device.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..4], &[0xa9, 0x00, 7, 1]);
+            assert_eq!(len, 4 + Point::SIZE);
+            device.send_to(&[0xa9, 0xe8, 0x03], src).await.unwrap();
+        });
+        let data = SampleData {
+            message_num: 7,
+            frame_num: 1,
+            points: vec![Point::CENTER_BLANK],
+        };
+        client.send_sample_data(data).await.unwrap();
+        let timeout = Duration::from_secs(1);
+        let free = client.recv_buffer_free(timeout).await.unwrap();
+        assert_eq!(free, Some(1000));
+        responder.await.unwrap();
+
+        // No further responses.
+        let timeout = Duration::from_millis(20);
+        assert_eq!(client.recv_buffer_free(timeout).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_send_sample_data_oversized() {
+        let (device, client) = mock_device().await;
+        let data = SampleData {
+            message_num: 0,
+            frame_num: 0,
+            points: vec![Point::CENTER_BLANK; MAX_POINTS_PER_MESSAGE + 1],
+        };
+        let result = client.send_sample_data(data).await;
+        assert!(matches!(
+            result,
+            Err(CommandError::Invalid(CommandValidationError::TooManyPoints { count, max }))
+                if count == MAX_POINTS_PER_MESSAGE + 1 && max == MAX_POINTS_PER_MESSAGE
+        ));
+
+        // Nothing was sent.
+        let mut buf = [0u8; 64];
+        let recv = device.recv_from(&mut buf);
+        assert!(tokio::time::timeout(Duration::from_millis(20), recv)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let (device, client) = mock_device().await;
+        assert_eq!(client.metrics(), ClientMetricsSnapshot::default());
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            for _ in 0..2 {
+                let (len, src) = device.recv_from(&mut buf).await.unwrap();
+                device.send_to(&buf[..len], src).await.unwrap();
+            }
+            let (len, src) = device.recv_from(&mut buf).await.unwrap();
+            assert_eq!(len, 4 + Point::SIZE);
+            device.send_to(&[0xa9, 0xe8, 0x03], src).await.unwrap();
+        });
+        client.set_output(true).await.unwrap();
+        client.enable_buffer_size_response(true).await.unwrap();
+        let data = SampleData {
+            message_num: 0,
+            frame_num: 0,
+            points: vec![Point::CENTER_BLANK],
+        };
+        client.send_sample_data(data).await.unwrap();
+        let timeout = Duration::from_secs(1);
+        assert_eq!(client.recv_buffer_free(timeout).await.unwrap(), Some(1000));
+        responder.await.unwrap();
+
+        // Nothing responds, so every attempt times out.
+        let timeout = Duration::from_millis(10);
+        let result = client
+            .send_command_with_retries(Command::GetFullInfo, 1, timeout)
+            .await;
+        assert!(matches!(result, Err(CommandError::Timeout(_))));
+
+        let expected = ClientMetricsSnapshot {
+            commands_sent: 4,
+            responses_received: 2,
+            data_packets_sent: 1,
+            buffer_free_responses: 1,
+            bytes_sent: (2 + 2 + 4 + Point::SIZE + 1 + 1) as u64,
+            retries: 1,
+            timeouts: 2,
+        };
+        assert_eq!(client.metrics(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_group_set_output_all() {
+        let localhost = Ipv4Addr::LOCALHOST;
+        let infos = [test_info(), test_info()];
+        let mut group = Group::from_infos(localhost.into(), &infos).await.unwrap();
+        assert_eq!(group.members().len(), 2);
+        assert_eq!(group.members()[0].buffer.free_space, 5000);
+
+        // Point each client at its own fake device.
+        let mut responders = Vec::new();
+        for (i, member) in group.members.iter_mut().enumerate() {
+            let device = UdpSocket::bind((localhost, 0)).await.unwrap();
+            let device_port = device.local_addr().unwrap().port();
+            member.client.target_addr.set_port(device_port);
+            // The second device echoes the command, but never reports its buffer.
+            responders.push(tokio::spawn(async move {
+                let mut buf = [0u8; 64];
+                let (len, src) = device.recv_from(&mut buf).await.unwrap();
+                assert_eq!(&buf[..len], &[0x80, 0x01]);
+                device.send_to(&buf[..len], src).await.unwrap();
+                let (_, src) = device.recv_from(&mut buf).await.unwrap();
+                if i == 0 {
+                    let reply = [0x8a, 0x00, 0xe8, 0x03];
+                    device.send_to(&reply, src).await.unwrap();
+                }
+            }));
+        }
+
+        let results = group.set_output_all(true).await;
+        assert_eq!(results.len(), 2);
+        for (ip, result) in results {
+            assert_eq!(ip, localhost);
+            result.unwrap();
+        }
+
+        let results = group.update_buffers().await;
+        assert!(matches!(results[0].1, Ok(1000)));
+        assert!(matches!(results[1].1, Err(CommandError::Timeout(_))));
+        assert_eq!(group.members()[0].buffer.free_space, 1000);
+        assert_eq!(group.members()[1].buffer.free_space, 5000);
+        for responder in responders {
+            responder.await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_device_span() {
+        let (logs, _guard) = capture_logs(tracing::Level::DEBUG);
+
+        let (device, client) = mock_device().await;
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device.send_to(&test_info().to_bytes(), src).await.unwrap();
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device
+                .send_to(&[0x8a, 0x00, 0xe8, 0x03], src)
+                .await
+                .unwrap();
+        });
+        client.get_full_info().await.unwrap();
+        client.get_buffer_free().await.unwrap();
+        responder.await.unwrap();
+
+        let logs = logs.contents();
+        let lines: Vec<&str> = logs.lines().filter(|l| l.contains("Sending")).collect();
+        assert_eq!(lines.len(), 2, "{logs}");
+        assert!(lines[0].contains("lasercube{ip=127.0.0.1}"), "{logs}");
+        assert!(
+            lines[1].contains("lasercube{ip=127.0.0.1 serial=[1, 2, 3, 4, 5, 6]}"),
+            "{logs}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_blank_output() {
+        let (device, client) = mock_device().await;
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 2048];
+            for i in 0..IDLE_MESSAGES as u8 {
+                let (len, _) = device.recv_from(&mut buf).await.unwrap();
+                assert_eq!(len, MAX_MESSAGE_SIZE);
+                assert_eq!(&buf[..4], &[0xa9, 0x00, i, 0]);
+                let points = lasercube_core::point::decode_all(&buf[4..len]).unwrap();
+                assert!(points.iter().all(|&p| p == Point::CENTER_BLANK));
+            }
+            let (len, src) = device.recv_from(&mut buf).await.unwrap();
+            assert_eq!(&buf[..len], &[0x80, 0x00]);
+            device.send_to(&buf[..len], src).await.unwrap();
+        });
+        client.blank_output().await.unwrap();
+        responder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unexpected_response_attached() {
+        let (device, client) = mock_device().await;
+        let responder = tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device.send_to(&test_info().to_bytes(), src).await.unwrap();
+        });
+        client
+            .send_sample_data(SampleData::idle(0, 0, 1))
+            .await
+            .unwrap();
+        let result = client.recv_buffer_free(Duration::from_secs(1)).await;
+        let Err(CommandError::UnexpectedResponse { expected, got }) = result else {
+            panic!("expected an unexpected response error, got {result:?}");
+        };
+        assert_eq!(expected, CommandType::SampleData);
+        assert_eq!(got, Response::FullInfo(test_info()));
+        responder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drain_pending() {
+        let (device, client) = mock_device().await;
+        assert_eq!(client.drain_pending(), 0);
+
+        // Stale responses on both the CMD and DATA sockets.
+        let cmd_addr = client.socket.local_addr().unwrap();
+        let data_addr = client.data_socket.local_addr().unwrap();
+        for _ in 0..3 {
+            device.send_to(&[0xa9, 0xe8, 0x03], cmd_addr).await.unwrap();
+        }
+        device
+            .send_to(&[0xa9, 0xe8, 0x03], data_addr)
+            .await
+            .unwrap();
+        client.socket.readable().await.unwrap();
+        client.data_socket.readable().await.unwrap();
+        // Let all of the datagrams arrive.
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        assert_eq!(client.drain_pending(), 4);
+        assert_eq!(client.drain_pending(), 0);
+        let timeout = Duration::from_millis(10);
+        assert_eq!(client.recv_buffer_free(timeout).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_addrs() {
+        let (device, client) = mock_device().await;
+        let local = client.local_addr().unwrap();
+        assert_ne!(local.port(), 0);
+        assert_eq!(local.ip(), IpAddr::V4(Ipv4Addr::LOCALHOST));
+        assert_eq!(client.target_addr(), device.local_addr().unwrap());
     }
 }
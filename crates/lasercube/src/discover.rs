@@ -1,12 +1,16 @@
 //! Device discovery.
 
-use crate::core;
-use futures::Stream;
+use crate::client::{warn_if_truncated, MAX_RESPONSE_SIZE};
+use futures::{Stream, StreamExt};
 use lasercube_core::cmds::{Command, Response};
-use lasercube_core::{cmds, port, LaserInfo};
+use lasercube_core::{cmds, hex_preview, LaserInfo, Ports};
+use std::collections::{HashMap, HashSet};
 use std::net::{IpAddr, Ipv4Addr, SocketAddr, SocketAddrV4};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::UdpSocket;
 use tokio::sync::mpsc;
+use tokio::time::Instant;
 use tokio_stream::wrappers::ReceiverStream;
 
 /// Error type for discovery operations
@@ -14,8 +18,50 @@ use tokio_stream::wrappers::ReceiverStream;
 pub enum DiscoveryError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
+    /// Broadcast was refused by the OS, e.g. due to missing permissions or a
+    /// firewall, as opposed to no devices replying.
+    #[error("Broadcast unavailable, check permissions and firewall settings: {0}")]
+    BroadcastUnavailable(#[source] std::io::Error),
     #[error("Response parse error: {0}")]
     Parse(#[from] cmds::ResponseParseError),
+    /// No device responded before the timeout.
+    #[error("No device found within {0:?}")]
+    NoDeviceFound(Duration),
+}
+
+/// Configuration for device discovery.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiscoveryConfig {
+    /// Capacity of the channel buffering discovered devices for the stream.
+    pub channel_capacity: usize,
+    /// If `Some`, the discovery packet is re-sent at this interval until the
+    /// stream is dropped, allowing devices that come online later to be found.
+    /// If `None`, the discovery packet is sent only once.
+    pub rebroadcast_interval: Option<Duration>,
+    /// Force broadcast to be enabled on the socket.
+    ///
+    /// Broadcast is always enabled for the global broadcast address and for
+    /// addresses ending in `.255`. Set this for directed broadcast addresses
+    /// of subnets that don't end in `.255` (e.g. `10.0.0.127` for a `/25`).
+    pub broadcast: bool,
+    /// The device ports. Discovery binds to and targets the CMD port.
+    pub ports: Ports,
+}
+
+impl DiscoveryConfig {
+    /// The default channel capacity.
+    pub const DEFAULT_CHANNEL_CAPACITY: usize = 32;
+}
+
+impl Default for DiscoveryConfig {
+    fn default() -> Self {
+        Self {
+            channel_capacity: Self::DEFAULT_CHANNEL_CAPACITY,
+            rebroadcast_interval: None,
+            broadcast: false,
+            ports: Ports::default(),
+        }
+    }
 }
 
 /// Discover LaserCube devices by sending a discovery packet to the given address.
@@ -24,6 +70,10 @@ pub enum DiscoveryError {
 /// that responds to the discovery query. The stream will continue producing
 /// values as long as responses are received.
 ///
+/// Devices are identified by serial number. A device is yielded again only
+/// when its identity changes (see [`LaserInfo::identity_eq`]), e.g. if it
+/// reconnects with a different IP, but not for telemetry like temperature.
+///
 /// # Example
 ///
 /// ```no_run
@@ -53,64 +103,130 @@ pub enum DiscoveryError {
 ///     Ok(())
 /// }
 /// ```
-#[tracing::instrument]
 pub async fn devices(
     bind_ip: IpAddr,
     target_ip: Ipv4Addr,
 ) -> Result<impl Stream<Item = LaserInfo>, DiscoveryError> {
-    // Create a socket for CMD port communications.
-    let bind_addr = SocketAddr::new(bind_ip, port::CMD);
-    tracing::debug!("Binding to UDP socket {bind_addr:?}");
-    let socket = UdpSocket::bind(bind_addr).await?;
+    devices_with_config(bind_ip, target_ip, DiscoveryConfig::default()).await
+}
 
-    // Enable broadcast if target is a broadcast address
-    if target_ip.is_broadcast() {
-        tracing::debug!("Enabling broadcast for UDP socket");
-        socket.set_broadcast(true)?;
-    }
+/// Discover LaserCube devices with the given configuration.
+///
+/// See [`devices`] and [`DiscoveryConfig`].
+#[tracing::instrument]
+pub async fn devices_with_config(
+    bind_ip: IpAddr,
+    target_ip: Ipv4Addr,
+    config: DiscoveryConfig,
+) -> Result<impl Stream<Item = LaserInfo>, DiscoveryError> {
+    discover(bind_ip, target_ip, config, |_| true).await
+}
+
+/// Discover LaserCube devices, only yielding those matching `filter`.
+///
+/// The filter is applied as responses are received, so rejected devices never
+/// reach the stream and aren't tracked for deduplication. See [`devices`].
+///
+/// # Example
+///
+/// ```no_run
+/// use futures::StreamExt;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let bind_ip = [0, 0, 0, 0].into();
+///     let target_ip = [255, 255, 255, 255].into();
+///     let filter = |info: &lasercube::core::LaserInfo| info.header.serial_number[0] == 0x42;
+///     let mut devices = lasercube::discover::devices_filtered(bind_ip, target_ip, filter).await?;
+///     while let Some(device_info) = devices.next().await {
+///         println!("Found LaserCube: {device_info:#?}");
+///     }
+///     Ok(())
+/// }
+/// ```
+#[tracing::instrument(skip(filter))]
+pub async fn devices_filtered(
+    bind_ip: IpAddr,
+    target_ip: Ipv4Addr,
+    filter: impl Fn(&LaserInfo) -> bool + Send + 'static,
+) -> Result<impl Stream<Item = LaserInfo>, DiscoveryError> {
+    discover(bind_ip, target_ip, DiscoveryConfig::default(), filter).await
+}
+
+/// Run discovery, yielding devices matching `filter`.
+async fn discover(
+    bind_ip: IpAddr,
+    target_ip: Ipv4Addr,
+    config: DiscoveryConfig,
+    filter: impl Fn(&LaserInfo) -> bool + Send + 'static,
+) -> Result<impl Stream<Item = LaserInfo>, DiscoveryError> {
+    let socket = Arc::new(bind_socket(bind_ip, target_ip, &config).await?);
 
     // Create a channel for the stream
-    let (tx, rx) = mpsc::channel(32);
+    let (tx, rx) = mpsc::channel(config.channel_capacity);
 
     // Create the GET_FULL_INFO command
     let cmd = Command::GetFullInfo;
     let cmd_bytes = cmd.to_bytes();
 
     // Send the command
-    let target_addr = SocketAddrV4::new(target_ip, core::port::CMD);
+    let target_addr = SocketAddrV4::new(target_ip, config.ports.cmd);
     tracing::debug!("Sending GET_FULL_INFO command to {target_addr:?}");
-    socket.send_to(&cmd_bytes, target_addr).await?;
+    send_discovery(&socket, &cmd_bytes, target_addr).await?;
+
+    // Spawn a task to periodically re-send the command until the stream closes
+    if let Some(interval) = config.rebroadcast_interval {
+        let socket = socket.clone();
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                if tx.is_closed() {
+                    break;
+                }
+                tracing::trace!("Re-sending GET_FULL_INFO command to {target_addr:?}");
+                if let Err(e) = socket.send_to(&cmd_bytes, target_addr).await {
+                    tracing::debug!("Failed to re-send on UDP socket: {e}");
+                    break;
+                }
+            }
+        });
+    }
 
     // Spawn a task to receive responses
     tokio::spawn(async move {
         // Create a buffer for receiving responses
-        let mut buf = vec![0u8; 1024];
+        let mut buf = vec![0u8; MAX_RESPONSE_SIZE];
         // Track discovered devices to avoid duplicates
-        let mut discovered = std::collections::HashMap::new();
+        let mut discovered = HashMap::new();
         // Continuously receive responses until the channel is closed
-        while !tx.is_closed() {
-            let (len, _src) = match socket.recv_from(&mut buf).await {
+        loop {
+            // Stop waiting as soon as the stream is dropped, releasing the socket.
+            let recv = tokio::select! {
+                res = socket.recv_from(&mut buf) => res,
+                _ = tx.closed() => break,
+            };
+            let (len, _src) = match recv {
                 Ok(ok) => ok,
                 Err(e) => {
                     tracing::debug!("Failed to recv on UDP socket: {e}");
                     break;
                 }
             };
-            let info = match Response::try_from(&buf[..len]) {
-                Ok(Response::FullInfo(info)) => info,
-                Ok(res) => {
-                    tracing::warn!("Unexpected response: {res:?}");
-                    continue;
-                }
-                // Failed to decode, we'll
-                Err(e) => {
-                    tracing::warn!("Failed to decode response: {e}");
-                    continue;
-                }
+            warn_if_truncated(len, &buf);
+            let Some(info) = parse_info(&buf[..len]) else {
+                continue;
             };
-            // If this is a new device or the info has changed, send it.
-            let key = info.header.ip_addr;
-            if discovered.get(&key) != Some(&info) {
+            if !filter(&info) {
+                tracing::trace!("Ignoring filtered device: {info:?}");
+                continue;
+            }
+            // If this is a new device or its identity has changed, send it.
+            // Devices are identified by serial number, as their IP may change.
+            // Telemetry like battery and temperature is ignored to avoid churn.
+            let key = info.header.serial_number;
+            let known = discovered.get(&key);
+            if !known.is_some_and(|known: &LaserInfo| known.identity_eq(&info)) {
                 tracing::debug!("Discovered new device: {info:?}");
                 discovered.insert(key, info.clone());
                 // If we can't send to the channel, it's been closed
@@ -126,3 +242,572 @@ pub async fn devices(
     // Return the stream
     Ok(ReceiverStream::new(rx))
 }
+
+/// Parse a response to `GetFullInfo`, logging and skipping anything else.
+///
+/// Responses with inconsistent fields are assumed to be corrupt and skipped
+/// too (see [`LaserInfoHeader::looks_valid`]).
+///
+/// [`LaserInfoHeader::looks_valid`]: lasercube_core::LaserInfoHeader::looks_valid
+fn parse_info(bytes: &[u8]) -> Option<LaserInfo> {
+    match Response::try_from(bytes) {
+        Ok(Response::FullInfo(info)) if info.header.looks_valid() => Some(info),
+        Ok(Response::FullInfo(info)) => {
+            tracing::warn!("Ignoring corrupt device info: {info:?}");
+            tracing::debug!("Corrupt response: [{}]", hex_preview(bytes));
+            None
+        }
+        Ok(res) => {
+            tracing::warn!("Unexpected response: {res:?}");
+            None
+        }
+        Err(e) => {
+            tracing::warn!("Failed to decode response: {e}");
+            tracing::debug!("Malformed response: [{}]", hex_preview(bytes));
+            None
+        }
+    }
+}
+
+/// Bind the socket used for discovery on the CMD port.
+async fn bind_socket(
+    bind_ip: IpAddr,
+    target_ip: Ipv4Addr,
+    config: &DiscoveryConfig,
+) -> Result<UdpSocket, DiscoveryError> {
+    // Create a socket for CMD port communications.
+    let bind_addr = SocketAddr::new(bind_ip, config.ports.cmd);
+    tracing::debug!("Binding to UDP socket {bind_addr:?}");
+    let socket = UdpSocket::bind(bind_addr).await?;
+
+    // Enable broadcast if target is a broadcast address
+    if config.broadcast || is_likely_broadcast(target_ip) {
+        tracing::debug!("Enabling broadcast for UDP socket");
+        socket
+            .set_broadcast(true)
+            .map_err(DiscoveryError::BroadcastUnavailable)?;
+    }
+
+    Ok(socket)
+}
+
+/// Send the discovery packet, distinguishing refused broadcasts from other
+/// I/O errors.
+async fn send_discovery(
+    socket: &UdpSocket,
+    bytes: &[u8],
+    target_addr: SocketAddrV4,
+) -> Result<(), DiscoveryError> {
+    match socket.send_to(bytes, target_addr).await {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            Err(DiscoveryError::BroadcastUnavailable(e))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Whether the address is the global broadcast address or likely a directed
+/// subnet broadcast address (e.g. `192.168.1.255`).
+fn is_likely_broadcast(ip: Ipv4Addr) -> bool {
+    ip.is_broadcast() || ip.octets()[3] == 255
+}
+
+/// Discover LaserCube devices for the given duration and collect the results.
+///
+/// Returns one `LaserInfo` per device serial number (the most recent response
+/// yielded by [`devices`] for that device), sorted by IP address.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let bind_ip = [0, 0, 0, 0].into();
+///     let target_ip = [255, 255, 255, 255].into();
+///     let duration = Duration::from_secs(3);
+///     for device_info in lasercube::discover::collect(bind_ip, target_ip, duration).await? {
+///         println!("Found LaserCube: {device_info:#?}");
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn collect(
+    bind_ip: IpAddr,
+    target_ip: Ipv4Addr,
+    duration: Duration,
+) -> Result<Vec<LaserInfo>, DiscoveryError> {
+    let stream = devices(bind_ip, target_ip).await?;
+    let mut stream = std::pin::pin!(stream);
+    let mut discovered = HashMap::new();
+    let _ = tokio::time::timeout(duration, async {
+        while let Some(info) = stream.next().await {
+            discovered.insert(info.header.serial_number, info);
+        }
+    })
+    .await;
+    let mut discovered: Vec<_> = discovered.into_values().collect();
+    discovered.sort_by_key(|info| info.header.ip_addr);
+    Ok(discovered)
+}
+
+/// Discover the first device to respond within `timeout`.
+///
+/// Convenient for single device setups. Returns
+/// [`DiscoveryError::NoDeviceFound`] if no device responds in time.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let bind_ip = [0, 0, 0, 0].into();
+///     let target_ip = [255, 255, 255, 255].into();
+///     let timeout = Duration::from_secs(5);
+///     let device_info = lasercube::discover::one(bind_ip, target_ip, timeout).await?;
+///     println!("Found LaserCube: {device_info:#?}");
+///     Ok(())
+/// }
+/// ```
+pub async fn one(
+    bind_ip: IpAddr,
+    target_ip: Ipv4Addr,
+    timeout: Duration,
+) -> Result<LaserInfo, DiscoveryError> {
+    let stream = devices(bind_ip, target_ip).await?;
+    let mut stream = std::pin::pin!(stream);
+    match tokio::time::timeout(timeout, stream.next()).await {
+        Ok(Some(info)) => Ok(info),
+        Ok(None) | Err(_) => Err(DiscoveryError::NoDeviceFound(timeout)),
+    }
+}
+
+/// Query devices at known IP addresses, without broadcasting.
+///
+/// Useful on networks that block broadcast. `GetFullInfo` is unicast to the
+/// CMD port of each target, and responses are collected until every target
+/// has responded or `timeout` elapses. Responses from addresses other than the
+/// targets are ignored.
+///
+/// Returns one `LaserInfo` per responding target, in the order of `targets`.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::net::Ipv4Addr;
+/// use std::time::Duration;
+///
+/// #[tokio::main]
+/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let bind_ip = [0, 0, 0, 0].into();
+///     let targets = [Ipv4Addr::new(192, 168, 1, 20), Ipv4Addr::new(192, 168, 1, 21)];
+///     let timeout = Duration::from_secs(1);
+///     for device_info in lasercube::discover::probe(bind_ip, &targets, timeout).await? {
+///         println!("Found LaserCube: {device_info:#?}");
+///     }
+///     Ok(())
+/// }
+/// ```
+pub async fn probe(
+    bind_ip: IpAddr,
+    targets: &[Ipv4Addr],
+    timeout: Duration,
+) -> Result<Vec<LaserInfo>, DiscoveryError> {
+    probe_with_ports(bind_ip, targets, timeout, Ports::default()).await
+}
+
+/// Query devices at known IP addresses, targeting the given ports.
+///
+/// See [`probe`]. Binds to and targets `ports.cmd`.
+#[tracing::instrument]
+pub async fn probe_with_ports(
+    bind_ip: IpAddr,
+    targets: &[Ipv4Addr],
+    timeout: Duration,
+    ports: Ports,
+) -> Result<Vec<LaserInfo>, DiscoveryError> {
+    let bind_addr = SocketAddr::new(bind_ip, ports.cmd);
+    tracing::debug!("Binding to UDP socket {bind_addr:?}");
+    let socket = UdpSocket::bind(bind_addr).await?;
+
+    let cmd_bytes = Command::GetFullInfo.to_bytes();
+    for &target_ip in targets {
+        let target_addr = SocketAddrV4::new(target_ip, ports.cmd);
+        tracing::debug!("Sending GET_FULL_INFO command to {target_addr:?}");
+        socket.send_to(&cmd_bytes, target_addr).await?;
+    }
+
+    let mut buf = vec![0u8; MAX_RESPONSE_SIZE];
+    let mut pending: HashSet<Ipv4Addr> = targets.iter().copied().collect();
+    let mut found = HashMap::new();
+    let deadline = Instant::now() + timeout;
+    while !pending.is_empty() {
+        let recv = socket.recv_from(&mut buf);
+        let Ok(res) = tokio::time::timeout_at(deadline, recv).await else {
+            break;
+        };
+        let (len, src) = match res {
+            Ok(ok) => ok,
+            // Some platforms report an unreachable target on the next receive.
+            // Keep waiting for the others.
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::ConnectionRefused
+                ) =>
+            {
+                tracing::debug!("Ignoring recv error: {e}");
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let src_ip = match src.ip() {
+            IpAddr::V4(ip) if pending.contains(&ip) => ip,
+            _ => {
+                tracing::trace!("Ignoring datagram from {src}");
+                continue;
+            }
+        };
+        warn_if_truncated(len, &buf);
+        if let Some(info) = parse_info(&buf[..len]) {
+            tracing::debug!("Found device at {src_ip}: {info:?}");
+            pending.remove(&src_ip);
+            found.insert(src_ip, info);
+        }
+    }
+    Ok(targets.iter().filter_map(|ip| found.remove(ip)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lasercube_core::{port, ConnectionType, LaserInfoHeader, StatusFlags};
+
+    fn test_laser_info(ip_addr: Ipv4Addr) -> LaserInfo {
+        LaserInfo {
+            header: LaserInfoHeader {
+                fw_major: 0,
+                fw_minor: 13,
+                status: StatusFlags::empty(),
+                dac_rate: 30_000,
+                max_dac_rate: 40_000,
+                rx_buffer_free: 6000,
+                rx_buffer_size: 6000,
+                battery_percent: 100,
+                temperature: 30,
+                model_number: 1,
+                conn_type: ConnectionType::Wifi,
+                serial_number: [1, 2, 3, 4, 5, 6],
+                ip_addr,
+            },
+            model_name: "LaserCube".to_string(),
+        }
+    }
+
+    // Relies on the whole 127.0.0.0/8 range being routed to loopback so that
+    // the fake device and discovery can both bind the CMD port.
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_rebroadcast_discovers_late_device() {
+        let bind_ip = Ipv4Addr::new(127, 0, 0, 5);
+        let device_ip = Ipv4Addr::new(127, 0, 0, 6);
+        let config = DiscoveryConfig {
+            rebroadcast_interval: Some(Duration::from_millis(20)),
+            ..Default::default()
+        };
+        let mut devices = devices_with_config(bind_ip.into(), device_ip, config)
+            .await
+            .unwrap();
+
+        // The device only comes online after the initial broadcast was sent.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let info = test_laser_info(device_ip);
+        let device = UdpSocket::bind((device_ip, port::CMD)).await.unwrap();
+        let reply = info.to_bytes();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device.send_to(&reply, src).await.unwrap();
+        });
+
+        let found = tokio::time::timeout(Duration::from_secs(1), devices.next())
+            .await
+            .expect("late device was not discovered");
+        assert_eq!(found, Some(info));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_collect() {
+        let bind_ip = Ipv4Addr::new(127, 0, 0, 3);
+        let device_ip = Ipv4Addr::new(127, 0, 0, 4);
+        let device = UdpSocket::bind((device_ip, port::CMD)).await.unwrap();
+        let info = test_laser_info(device_ip);
+        let first_reply = info.to_bytes();
+        let mut updated = info.clone();
+        updated.header.fw_minor += 1;
+        let second_reply = updated.to_bytes();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device.send_to(&first_reply, src).await.unwrap();
+            device.send_to(&second_reply, src).await.unwrap();
+        });
+
+        let duration = Duration::from_millis(200);
+        let found = collect(bind_ip.into(), device_ip, duration).await.unwrap();
+        assert_eq!(found, vec![updated]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_telemetry_changes_not_reemitted() {
+        let bind_ip = Ipv4Addr::new(127, 0, 0, 9);
+        let device_ip = Ipv4Addr::new(127, 0, 0, 10);
+        let device = UdpSocket::bind((device_ip, port::CMD)).await.unwrap();
+        let info = test_laser_info(device_ip);
+        let mut warmer = info.clone();
+        warmer.header.temperature += 5;
+        let mut other = test_laser_info(device_ip);
+        other.header.serial_number = [6, 5, 4, 3, 2, 1];
+        let replies = [info.to_bytes(), warmer.to_bytes(), other.to_bytes()];
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            for reply in replies {
+                device.send_to(&reply, src).await.unwrap();
+            }
+        });
+
+        let devices = devices(bind_ip.into(), device_ip).await.unwrap();
+        let found =
+            tokio::time::timeout(Duration::from_secs(1), devices.take(2).collect::<Vec<_>>())
+                .await
+                .unwrap();
+        assert_eq!(found, vec![info, other]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_collect_dedup_by_serial() {
+        let bind_ip = Ipv4Addr::new(127, 0, 0, 7);
+        let device_ip = Ipv4Addr::new(127, 0, 0, 8);
+        let device = UdpSocket::bind((device_ip, port::CMD)).await.unwrap();
+        // The same device reporting two different IPs, e.g. after reconnecting.
+        let first = test_laser_info(Ipv4Addr::new(10, 0, 0, 1));
+        let second = test_laser_info(Ipv4Addr::new(10, 0, 0, 2));
+        let mut other = test_laser_info(Ipv4Addr::new(10, 0, 0, 3));
+        other.header.serial_number = [6, 5, 4, 3, 2, 1];
+        let replies = [first.to_bytes(), second.to_bytes(), other.to_bytes()];
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            for reply in replies {
+                device.send_to(&reply, src).await.unwrap();
+            }
+        });
+
+        let duration = Duration::from_millis(200);
+        let found = collect(bind_ip.into(), device_ip, duration).await.unwrap();
+        assert_eq!(found, vec![second, other]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_one() {
+        let bind_ip = Ipv4Addr::new(127, 0, 0, 11);
+        let device_ip = Ipv4Addr::new(127, 0, 0, 12);
+        let device = UdpSocket::bind((device_ip, port::CMD)).await.unwrap();
+        let info = test_laser_info(device_ip);
+        let reply = info.to_bytes();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device.send_to(&reply, src).await.unwrap();
+        });
+
+        let timeout = Duration::from_secs(1);
+        let found = one(bind_ip.into(), device_ip, timeout).await.unwrap();
+        assert_eq!(found, info);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_one_no_device_found() {
+        let bind_ip = Ipv4Addr::new(127, 0, 0, 13);
+        let device_ip = Ipv4Addr::new(127, 0, 0, 14);
+        let timeout = Duration::from_millis(100);
+        let result = one(bind_ip.into(), device_ip, timeout).await;
+        assert!(
+            matches!(result, Err(DiscoveryError::NoDeviceFound(t)) if t == timeout),
+            "{result:?}"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_devices_filtered() {
+        let bind_ip = Ipv4Addr::new(127, 0, 0, 17);
+        let device_ip = Ipv4Addr::new(127, 0, 0, 18);
+        let device = UdpSocket::bind((device_ip, port::CMD)).await.unwrap();
+        // Two devices responding, distinguished by serial number.
+        let rejected = test_laser_info(device_ip);
+        let mut admitted = test_laser_info(device_ip);
+        admitted.header.serial_number = [0x42, 0, 0, 0, 0, 1];
+        let replies = [rejected.to_bytes(), admitted.to_bytes()];
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            for reply in replies {
+                device.send_to(&reply, src).await.unwrap();
+            }
+        });
+
+        let filter = |info: &LaserInfo| info.header.serial_number[0] == 0x42;
+        let mut devices = devices_filtered(bind_ip.into(), device_ip, filter)
+            .await
+            .unwrap();
+        let found = tokio::time::timeout(Duration::from_secs(1), devices.next())
+            .await
+            .unwrap();
+        assert_eq!(found, Some(admitted));
+        let next = tokio::time::timeout(Duration::from_millis(100), devices.next()).await;
+        assert!(next.is_err(), "{next:?}");
+    }
+
+    #[test]
+    fn test_parse_info_rejects_corrupt() {
+        let info = test_laser_info(Ipv4Addr::LOCALHOST);
+        assert_eq!(parse_info(&info.to_bytes()), Some(info.clone()));
+        let mut corrupt = info;
+        corrupt.header.battery_percent = 200;
+        assert_eq!(parse_info(&corrupt.to_bytes()), None);
+        assert_eq!(parse_info(&[0x78, 0x00]), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_custom_cmd_port() {
+        let bind_ip = Ipv4Addr::new(127, 0, 0, 15);
+        let device_ip = Ipv4Addr::new(127, 0, 0, 16);
+        let device = UdpSocket::bind((device_ip, 0)).await.unwrap();
+        let config = DiscoveryConfig {
+            ports: Ports {
+                cmd: device.local_addr().unwrap().port(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let info = test_laser_info(device_ip);
+        let reply = info.to_bytes();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device.send_to(&reply, src).await.unwrap();
+        });
+
+        let mut devices = devices_with_config(bind_ip.into(), device_ip, config)
+            .await
+            .unwrap();
+        let found = tokio::time::timeout(Duration::from_secs(1), devices.next())
+            .await
+            .unwrap();
+        assert_eq!(found, Some(info));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_probe() {
+        let bind_ip = Ipv4Addr::new(127, 0, 0, 19);
+        let device_ips = [Ipv4Addr::new(127, 0, 0, 20), Ipv4Addr::new(127, 0, 0, 21)];
+        let mut infos = vec![];
+        for (i, &device_ip) in device_ips.iter().enumerate() {
+            let device = UdpSocket::bind((device_ip, port::CMD)).await.unwrap();
+            let mut info = test_laser_info(device_ip);
+            info.header.serial_number[5] += i as u8;
+            let reply = info.to_bytes();
+            infos.push(info);
+            tokio::spawn(async move {
+                let mut buf = [0u8; 64];
+                let (_, src) = device.recv_from(&mut buf).await.unwrap();
+                device.send_to(&reply, src).await.unwrap();
+            });
+        }
+
+        // A target without a device doesn't prevent collecting the others.
+        let targets = [device_ips[1], Ipv4Addr::new(127, 0, 0, 22), device_ips[0]];
+        let timeout = Duration::from_millis(200);
+        let found = probe(bind_ip.into(), &targets, timeout).await.unwrap();
+        assert_eq!(found, vec![infos[1].clone(), infos[0].clone()]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_probe_custom_cmd_port() {
+        let bind_ip = Ipv4Addr::new(127, 0, 0, 23);
+        let device_ip = Ipv4Addr::new(127, 0, 0, 24);
+        let device = UdpSocket::bind((device_ip, 0)).await.unwrap();
+        let ports = Ports {
+            cmd: device.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+        let info = test_laser_info(device_ip);
+        let reply = info.to_bytes();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 64];
+            let (_, src) = device.recv_from(&mut buf).await.unwrap();
+            device.send_to(&reply, src).await.unwrap();
+        });
+
+        let timeout = Duration::from_secs(1);
+        let found = probe_with_ports(bind_ip.into(), &[device_ip], timeout, ports)
+            .await
+            .unwrap();
+        assert_eq!(found, vec![info]);
+    }
+
+    // Linux refuses to send to a broadcast address without `SO_BROADCAST`.
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_broadcast_unavailable() {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let target_addr = SocketAddrV4::new(Ipv4Addr::new(127, 255, 255, 255), port::CMD);
+        let bytes = Command::GetFullInfo.to_bytes();
+        let result = send_discovery(&socket, &bytes, target_addr).await;
+        assert!(
+            matches!(result, Err(DiscoveryError::BroadcastUnavailable(_))),
+            "{result:?}"
+        );
+        // The OS error is kept as the source for error reports.
+        let err = result.unwrap_err();
+        let source = std::error::Error::source(&err).unwrap();
+        let io_err = source.downcast_ref::<std::io::Error>().unwrap();
+        assert_eq!(io_err.kind(), std::io::ErrorKind::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn test_directed_broadcast_enabled() {
+        let bind_ip = Ipv4Addr::LOCALHOST.into();
+        let config = DiscoveryConfig::default();
+        let target_ip = Ipv4Addr::new(192, 168, 1, 255);
+        let socket = bind_socket(bind_ip, target_ip, &config).await.unwrap();
+        assert!(socket.broadcast().unwrap());
+        drop(socket);
+
+        // Unicast targets don't enable broadcast unless forced.
+        let target_ip = Ipv4Addr::new(10, 0, 0, 127);
+        let socket = bind_socket(bind_ip, target_ip, &config).await.unwrap();
+        assert!(!socket.broadcast().unwrap());
+        drop(socket);
+        let config = DiscoveryConfig {
+            broadcast: true,
+            ..Default::default()
+        };
+        let socket = bind_socket(bind_ip, target_ip, &config).await.unwrap();
+        assert!(socket.broadcast().unwrap());
+    }
+}
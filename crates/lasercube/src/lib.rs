@@ -2,6 +2,8 @@
 
 pub use client::Client;
 pub use lasercube_core as core;
+pub use metrics::{ClientMetrics, ClientMetricsSnapshot};
 
 pub mod client;
 pub mod discover;
+pub mod metrics;
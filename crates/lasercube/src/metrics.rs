@@ -0,0 +1,80 @@
+//! Counters for observing a [`Client`](crate::Client)'s traffic.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Counters tracking the traffic of a single [`Client`](crate::Client).
+///
+/// Each counter is only ever incremented with a relaxed atomic add, so reading
+/// them via [`ClientMetrics::snapshot`] is cheap but not synchronized across
+/// counters.
+#[derive(Debug, Default)]
+pub struct ClientMetrics {
+    commands_sent: AtomicU64,
+    responses_received: AtomicU64,
+    data_packets_sent: AtomicU64,
+    buffer_free_responses: AtomicU64,
+    bytes_sent: AtomicU64,
+    retries: AtomicU64,
+    timeouts: AtomicU64,
+}
+
+/// A point-in-time copy of a [`ClientMetrics`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClientMetricsSnapshot {
+    /// Commands sent to the CMD port.
+    pub commands_sent: u64,
+    /// Datagrams received on the CMD port, including discarded responses.
+    pub responses_received: u64,
+    /// `SampleData` messages sent to the DATA port.
+    pub data_packets_sent: u64,
+    /// Buffer size responses received on the DATA port.
+    pub buffer_free_responses: u64,
+    /// Total bytes sent to both the CMD and DATA ports.
+    pub bytes_sent: u64,
+    /// Commands re-sent by [`Client::send_command_with_retries`](crate::Client::send_command_with_retries).
+    pub retries: u64,
+    /// Commands for which no response arrived within the timeout.
+    pub timeouts: u64,
+}
+
+impl ClientMetrics {
+    /// Read the current value of all counters.
+    pub fn snapshot(&self) -> ClientMetricsSnapshot {
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        ClientMetricsSnapshot {
+            commands_sent: load(&self.commands_sent),
+            responses_received: load(&self.responses_received),
+            data_packets_sent: load(&self.data_packets_sent),
+            buffer_free_responses: load(&self.buffer_free_responses),
+            bytes_sent: load(&self.bytes_sent),
+            retries: load(&self.retries),
+            timeouts: load(&self.timeouts),
+        }
+    }
+
+    pub(crate) fn command_sent(&self, bytes: usize) {
+        self.commands_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn response_received(&self) {
+        self.responses_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn data_packet_sent(&self, bytes: usize) {
+        self.data_packets_sent.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn buffer_free_received(&self) {
+        self.buffer_free_responses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn timeout(&self) {
+        self.timeouts.fetch_add(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,31 @@
+//! Compile check ensuring `lasercube-core` builds for `no_std + alloc`.
+//!
+//! Build this crate on its own so that `lasercube-core`'s `std` feature is not
+//! unified in from the rest of the workspace:
+//!
+//! ```sh
+//! cargo build -p lasercube-no-std-check
+//! ```
+
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec;
+use lasercube_core::cmds::SerializeError;
+use lasercube_core::{Command, Point, SampleData, StatusFlags};
+
+/// Encode a single centered, blank point as a data message into `buffer`.
+pub fn encode_blank_point(buffer: &mut [u8]) -> Result<usize, SerializeError> {
+    let data = SampleData {
+        message_num: 0,
+        frame_num: 0,
+        points: vec![Point::CENTER_BLANK],
+    };
+    Command::SampleData(data).write_to_slice(buffer)
+}
+
+/// Decode whether output is enabled from a raw status byte.
+pub fn output_enabled(status: u8) -> bool {
+    StatusFlags::from_bits_retain(status).output_enabled()
+}